@@ -0,0 +1,169 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use crate::mesh::ImportOptions;
+use crate::obj::{Obj, ObjError, TextureCache};
+
+// Variable de entorno con la carpeta de recursos; tiene prioridad sobre la búsqueda automática
+pub const ASSETS_ENV: &str = "SPACESHIP_ASSETS";
+
+// Cada cuánto se revisan las fechas de modificación de los archivos vigilados
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ModelHandle(usize);
+
+// Carpeta de recursos cuando no se configura una: la de la variable de entorno, o la primera
+// carpeta `assets` que exista junto al ejecutable, en el crate (en depuración) o en el
+// directorio actual
+pub fn locate_root() -> PathBuf {
+    if let Some(root) = env::var_os(ASSETS_ENV) {
+        return PathBuf::from(root);
+    }
+    let mut candidates = Vec::new();
+    if let Some(exe_dir) = env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+        candidates.push(exe_dir.join("assets"));
+    }
+    // Al correr con cargo el ejecutable queda en target/, lejos de los recursos del crate. Sólo
+    // en las compilaciones de depuración: una release no depende de dónde se compiló
+    #[cfg(debug_assertions)]
+    candidates.push(Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+    candidates.push(PathBuf::from("assets"));
+    candidates.iter()
+        .find(|dir| dir.is_dir())
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("assets"))
+}
+
+// Archivo vigilado con la fecha de modificación que tenía al cargarse (`None` si no existía)
+struct Watched {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Watched {
+    fn new(path: PathBuf) -> Self {
+        let modified = modified_time(&path);
+        Watched { path, modified }
+    }
+
+    fn changed(&self) -> bool {
+        modified_time(&self.path) != self.modified
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+struct ModelEntry {
+    path: PathBuf,
+    options: ImportOptions,
+    model: Obj,
+    sources: Vec<Watched>,
+}
+
+// Carga los modelos una sola vez (se piden de nuevo por su handle) y los recarga cuando cambia
+// alguno de sus archivos (el propio, las bibliotecas MTL o las texturas) mientras el programa
+// corre. Las texturas no tienen handle propio: se piden siempre a través de un modelo.
+pub struct AssetManager {
+    root: PathBuf,
+    models: Vec<ModelEntry>,
+    // Texturas de los materiales de los modelos por ruta, compartidas entre ellos
+    material_textures: TextureCache,
+    last_poll: Instant,
+}
+
+impl AssetManager {
+    pub fn new(root: PathBuf) -> Self {
+        AssetManager {
+            root,
+            models: Vec::new(),
+            material_textures: TextureCache::new(),
+            last_poll: Instant::now(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // Las rutas relativas son relativas a la carpeta de recursos
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    // Carga un modelo, o devuelve el que ya se cargó con la misma ruta y opciones
+    pub fn load_model(&mut self, path: &str, options: &ImportOptions) -> Result<ModelHandle, ObjError> {
+        let path = self.resolve(path);
+        if let Some(index) = self.models.iter().position(|entry| entry.path == path && entry.options == *options) {
+            return Ok(ModelHandle(index));
+        }
+        let model = Obj::load(&path.to_string_lossy(), options, &mut self.material_textures)?;
+        Ok(self.insert_model(path, *options, model))
+    }
+
+    // Registra un modelo de reemplazo para un archivo que no se pudo cargar; el archivo queda
+    // vigilado y, cuando se arregla, se carga en lugar del reemplazo
+    pub fn insert_fallback(&mut self, path: &str, options: &ImportOptions, model: Obj) -> ModelHandle {
+        let path = self.resolve(path);
+        self.insert_model(path, *options, model)
+    }
+
+    fn insert_model(&mut self, path: PathBuf, options: ImportOptions, model: Obj) -> ModelHandle {
+        let sources = watch_sources(&path, &model);
+        self.models.push(ModelEntry { path, options, model, sources });
+        ModelHandle(self.models.len() - 1)
+    }
+
+    pub fn model(&self, handle: ModelHandle) -> &Obj {
+        &self.models[handle.0].model
+    }
+
+    // Revisa (como mucho cada `POLL_INTERVAL`) si cambió algún archivo vigilado y recarga lo
+    // que depende de él. Devuelve los modelos recargados, para que se actualice lo derivado
+    // de ellos. Si la recarga falla se avisa y se mantiene la versión anterior.
+    pub fn poll_changes(&mut self) -> Vec<ModelHandle> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        // Las texturas modificadas salen de la caché antes de recargar los modelos que las usan
+        for source in self.models.iter().flat_map(|entry| &entry.sources) {
+            if source.changed() {
+                self.material_textures.remove(&source.path);
+            }
+        }
+
+        let mut reloaded = Vec::new();
+        for (index, entry) in self.models.iter_mut().enumerate() {
+            if !entry.sources.iter().any(Watched::changed) {
+                continue;
+            }
+            match Obj::load(&entry.path.to_string_lossy(), &entry.options, &mut self.material_textures) {
+                Ok(model) => {
+                    println!("Modelo recargado: {}: {}", entry.path.display(), model.report());
+                    entry.sources = watch_sources(&entry.path, &model);
+                    entry.model = model;
+                    reloaded.push(ModelHandle(index));
+                }
+                Err(err) => {
+                    eprintln!("Error recargando {}: {}; se mantiene la versión anterior", entry.path.display(), err);
+                    // No se reintenta hasta el próximo cambio
+                    for source in &mut entry.sources {
+                        *source = Watched::new(source.path.clone());
+                    }
+                }
+            }
+        }
+        reloaded
+    }
+}
+
+// Archivos de los que depende un modelo; el de reemplazo sólo vigila la ruta que falló
+fn watch_sources(path: &Path, model: &Obj) -> Vec<Watched> {
+    let sources = if model.sources().is_empty() { vec![path.to_path_buf()] } else { model.sources().to_vec() };
+    sources.into_iter().map(Watched::new).collect()
+}
@@ -0,0 +1,151 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::Uniforms;
+
+// Parámetros de la capa atmosférica de un planeta
+#[derive(Clone, Copy)]
+pub struct Atmosphere {
+    // Color de dispersión Rayleigh: los canales más altos se dispersan más
+    pub color: Vec3,
+    // Multiplicador de la densidad de la capa
+    pub density: f32,
+    // Espesor de la capa como fracción del radio del planeta
+    pub thickness: f32,
+    // Peso de la dispersión Mie (neblina, polvo) frente a la Rayleigh
+    pub mie: f32,
+}
+
+impl Atmosphere {
+    // Cielo azul con un halo fino, atardecer rojizo en el terminador
+    pub fn earth_like() -> Self {
+        Atmosphere {
+            color: Vec3::new(0.18, 0.45, 1.0),
+            density: 1.2,
+            thickness: 0.15,
+            mie: 0.2,
+        }
+    }
+
+    // Capa gruesa y difusa, dominada por neblina
+    pub fn hazy() -> Self {
+        Atmosphere {
+            color: Vec3::new(0.55, 0.7, 1.0),
+            density: 0.8,
+            thickness: 0.12,
+            mie: 0.8,
+        }
+    }
+
+    pub fn shell_scale(&self) -> f32 {
+        1.0 + self.thickness
+    }
+}
+
+const SAMPLES: usize = 8;
+// Altura de escala relativa al espesor de la capa
+const SCALE_HEIGHT: f32 = 0.25;
+// Asimetría de Henyey-Greenstein para Mie (dispersión hacia adelante)
+const MIE_G: f32 = 0.76;
+
+// Dispersión simple aproximada a lo largo del rayo de la cámara que atraviesa la capa.
+// La capa se dibuja como una esfera más grande que el planeta con mezcla aditiva; cada
+// píxel integra la luz dispersada en todo el tramo del rayo dentro de la atmósfera, así
+// que sólo se evalúa una cara de la esfera (la frontal desde fuera, la trasera desde dentro).
+pub fn atmosphere_shader(fragment: &Fragment, uniforms: &Uniforms, atmosphere: &Atmosphere) -> (Color, f32) {
+    let center = uniforms.model_matrix.column(3).xyz();
+    let shell_radius = uniforms.model_matrix.column(0).xyz().magnitude();
+    let planet_radius = shell_radius / atmosphere.shell_scale();
+    let layer = shell_radius - planet_radius;
+
+    let origin = uniforms.camera_position;
+    let to_fragment = fragment.world_position - origin;
+    if to_fragment.magnitude() < 1e-5 {
+        return (Color::black(), 0.0);
+    }
+    let direction = to_fragment.normalize();
+
+    let camera_inside = (origin - center).magnitude() < shell_radius;
+    let front_face = (fragment.world_position - center).dot(&direction) < 0.0;
+    if camera_inside == front_face {
+        return (Color::black(), 0.0);
+    }
+
+    let Some((shell_near, shell_far)) = ray_sphere(origin, direction, center, shell_radius) else {
+        return (Color::black(), 0.0);
+    };
+    let start = shell_near.max(0.0);
+    let end = match ray_sphere(origin, direction, center, planet_radius) {
+        Some((planet_near, _)) if planet_near > 0.0 => shell_far.min(planet_near),
+        _ => shell_far,
+    };
+    if end <= start {
+        return (Color::black(), 0.0);
+    }
+
+    // Coeficientes por unidad de mundo, normalizados por el espesor para que el aspecto
+    // no dependa del tamaño del planeta
+    let beta_rayleigh = atmosphere.color * (atmosphere.density / layer);
+    let beta_mie = atmosphere.mie * atmosphere.density / layer;
+
+    let step = (end - start) / SAMPLES as f32;
+    let mut view_depth = 0.0;
+    let mut inscattered = Vec3::zeros();
+    let mut light_dir = Vec3::zeros();
+
+    for i in 0..SAMPLES {
+        let sample = origin + direction * (start + step * (i as f32 + 0.5));
+        let from_center = sample - center;
+        let altitude = ((from_center.magnitude() - planet_radius) / layer).clamp(0.0, 1.0);
+        let density = (-altitude / SCALE_HEIGHT).exp() * step;
+        view_depth += density;
+
+        light_dir = (uniforms.light_position - sample).normalize();
+        let up = from_center.normalize();
+        let cos_sun = up.dot(&light_dir);
+
+        // Muestras a la sombra del planeta no reciben luz
+        let horizon = -(1.0 - (planet_radius / from_center.magnitude()).powi(2)).max(0.0).sqrt();
+        if cos_sun < horizon {
+            continue;
+        }
+
+        // Profundidad óptica hacia el sol: crece rápido cuando el sol está bajo (terminador)
+        let sun_depth = (-altitude / SCALE_HEIGHT).exp() * layer * SCALE_HEIGHT / (cos_sun - horizon + 0.05);
+
+        let optical_depth = view_depth + sun_depth;
+        let extinction = beta_rayleigh * optical_depth + Vec3::repeat(beta_mie * optical_depth * 1.1);
+        let transmittance = extinction.map(|e| (-e).exp());
+
+        inscattered += transmittance * density;
+    }
+
+    let cos_theta = direction.dot(&light_dir);
+    let phase_rayleigh = 3.0 / (16.0 * PI) * (1.0 + cos_theta * cos_theta);
+    let g2 = MIE_G * MIE_G;
+    let phase_mie = 3.0 / (8.0 * PI) * ((1.0 - g2) * (1.0 + cos_theta * cos_theta))
+        / ((2.0 + g2) * (1.0 + g2 - 2.0 * MIE_G * cos_theta).powf(1.5));
+
+    let scattered = (inscattered.component_mul(&beta_rayleigh) * phase_rayleigh
+        + inscattered * (beta_mie * phase_mie)).component_mul(&uniforms.light_color);
+
+    // Exposición simple para mantener el halo en rango sin recortar
+    let exposure = 12.0;
+    let color = scattered.map(|c| 1.0 - (-c * exposure).exp());
+
+    (Color::from_float(color.x, color.y, color.z), 1.0)
+}
+
+// Distancias de entrada y salida de un rayo en una esfera
+fn ray_sphere(origin: Vec3, direction: Vec3, center: Vec3, radius: f32) -> Option<(f32, f32)> {
+    let offset = origin - center;
+    let b = offset.dot(&direction);
+    let c = offset.dot(&offset) - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some((-b - root, -b + root))
+}
@@ -0,0 +1,223 @@
+use std::f32::consts::PI;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::fragment_shader::{desert_surface, moon_surface, volcanic_surface, ShaderType};
+use crate::planet::procedural_surface;
+use crate::texture::{write_png, Texture, TextureError, WrapMode};
+
+// Parte estática de una superficie procedural, en función de la dirección sobre la esfera:
+// lo que se puede hornear. La luz y las capas animadas se calculan en cada cuadro.
+#[derive(Clone, Copy, Default)]
+pub struct Surface {
+    // Color propio en [0, 1]
+    pub albedo: Vec3,
+    // Normal con el relieve, en espacio modelo
+    pub normal: Vec3,
+    // Dato extra que usa cada shader (agua que refleja, grietas de lava...)
+    pub mask: f32,
+}
+
+// Cómo se reparte la esfera en la textura
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    // Longitud y latitud, como las UV de `create_sphere`; comprime los polos
+    Equirectangular,
+    // Seis caras de un cubo proyectadas sobre la esfera; densidad de texels más pareja
+    Cubemap,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BakeSettings {
+    pub projection: Projection,
+    // Alto de la textura equirectangular (el ancho es el doble) o lado de cada cara del cubo
+    pub resolution: usize,
+}
+
+impl BakeSettings {
+    pub fn equirectangular(resolution: usize) -> Self {
+        BakeSettings { projection: Projection::Equirectangular, resolution }
+    }
+
+    pub fn cubemap(resolution: usize) -> Self {
+        BakeSettings { projection: Projection::Cubemap, resolution }
+    }
+}
+
+// Texturas de albedo, normal y máscara de una cara (o de toda la esfera, en equirectangular)
+struct SurfaceMaps {
+    albedo: Texture,
+    // Normal en espacio modelo codificada en [0, 1], como los mapas de normales
+    normal: Texture,
+    mask: Texture,
+}
+
+impl SurfaceMaps {
+    fn bake<F: Fn(f32, f32) -> Vec3 + Sync>(width: usize, height: usize, wrap: WrapMode, surface: &(dyn Fn(&Vec3) -> Surface + Sync), direction: F) -> Self {
+        let samples = parallel_rows(width, height, |x, y| {
+            let u = (x as f32 + 0.5) / width as f32;
+            let v = (y as f32 + 0.5) / height as f32;
+            surface(&direction(u, v))
+        });
+        let map = |channel: &dyn Fn(&Surface) -> Vec3| {
+            Texture::from_rgb(width, height, samples.iter().map(channel).collect()).with_wrap(wrap)
+        };
+        SurfaceMaps {
+            albedo: map(&|sample| sample.albedo),
+            normal: map(&|sample| sample.normal * 0.5 + Vec3::repeat(0.5)),
+            mask: map(&|sample| Vec3::repeat(sample.mask)),
+        }
+    }
+
+    // Textura por el sufijo con el que se guarda
+    fn map(&self, name: &str) -> &Texture {
+        match name {
+            "albedo" => &self.albedo,
+            "normal" => &self.normal,
+            _ => &self.mask,
+        }
+    }
+
+    fn sample(&self, uv: Vec2, uv_footprint: f32) -> Surface {
+        let normal = self.normal.sample(uv, uv_footprint) * 2.0 - Vec3::repeat(1.0);
+        Surface {
+            albedo: self.albedo.sample(uv, uv_footprint),
+            normal: if normal.magnitude() > 1e-6 { normal.normalize() } else { normal },
+            mask: self.mask.sample(uv, uv_footprint).x,
+        }
+    }
+}
+
+// Superficie de un shader procedural evaluada una sola vez sobre la esfera
+pub struct BakedSurface {
+    pub settings: BakeSettings,
+    // Una entrada en equirectangular; seis caras (+X, -X, +Y, -Y, +Z, -Z) en cubemap
+    faces: Vec<SurfaceMaps>,
+}
+
+impl BakedSurface {
+    // `None` si la superficie del shader está animada entera y no se puede hornear
+    pub fn bake(shader_type: &ShaderType, settings: BakeSettings) -> Option<Self> {
+        let surface: Box<dyn Fn(&Vec3) -> Surface + Sync> = match shader_type {
+            ShaderType::Moon => Box::new(moon_surface),
+            ShaderType::Desert => Box::new(desert_surface),
+            ShaderType::Volcanic => Box::new(volcanic_surface),
+            ShaderType::Procedural(params) => {
+                let params = *params;
+                Box::new(move |direction: &Vec3| procedural_surface(&params, direction))
+            }
+            _ => return None,
+        };
+        let surface = surface.as_ref();
+
+        let size = settings.resolution.max(4);
+        let faces = match settings.projection {
+            // Se repite en los dos ejes: la costura de longitud 0 se mezcla sin cortes
+            Projection::Equirectangular => vec![SurfaceMaps::bake(size * 2, size, WrapMode::Repeat, surface, |u, v| {
+                let (sin_theta, cos_theta) = (PI * v).sin_cos();
+                let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+                Vec3::new(sin_theta * cos_phi, cos_theta, sin_theta * sin_phi)
+            })],
+            Projection::Cubemap => (0..6)
+                .map(|face| SurfaceMaps::bake(size, size, WrapMode::ClampToEdge, surface, |u, v| cube_direction(face, u * 2.0 - 1.0, v * 2.0 - 1.0).normalize()))
+                .collect(),
+        };
+        Some(BakedSurface { settings, faces })
+    }
+
+    // Superficie en un punto de la esfera del modelo; `uv_footprint` es el de las UV de la esfera
+    pub fn sample(&self, position: &Vec3, uv_footprint: f32) -> Surface {
+        let direction = position.normalize();
+        match self.settings.projection {
+            Projection::Equirectangular => {
+                let u = (direction.z.atan2(direction.x) / (2.0 * PI)).rem_euclid(1.0);
+                let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+                self.faces[0].sample(Vec2::new(u, v), uv_footprint)
+            }
+            Projection::Cubemap => {
+                let (face, uv) = cube_face(&direction);
+                // Área UV por estereorradián de una cara respecto de la equirectangular en esta latitud
+                let footprint = uv_footprint * 3.0 * PI * (1.0 - direction.y * direction.y).max(0.0).sqrt();
+                self.faces[face].sample(uv, footprint)
+            }
+        }
+    }
+
+    // Guarda albedo, normal y máscara como `<name>_albedo.png`, `<name>_normal.png` y
+    // `<name>_mask.png`; las caras del cubemap van en una tira horizontal (+X, -X, +Y, -Y, +Z, -Z)
+    pub fn save_png(&self, dir: &Path, name: &str) -> Result<(), TextureError> {
+        for suffix in ["albedo", "normal", "mask"] {
+            let path = dir.join(format!("{}_{}.png", name, suffix));
+            let face_width = self.faces[0].map(suffix).width();
+            let height = self.faces[0].map(suffix).height();
+            let width = face_width * self.faces.len();
+            let mut texels = Vec::with_capacity(width * height);
+            for y in 0..height {
+                for face in &self.faces {
+                    texels.extend_from_slice(&face.map(suffix).texels()[y * face_width..(y + 1) * face_width]);
+                }
+            }
+            write_png(&path, width, height, &texels)?;
+        }
+        Ok(())
+    }
+}
+
+// Dirección (sin normalizar) de un punto de una cara del cubo, con s y t en [-1, 1]
+fn cube_direction(face: usize, s: f32, t: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    }
+}
+
+// Cara del cubo hacia la que apunta la dirección y su UV en esa cara (inversa de `cube_direction`)
+fn cube_face(direction: &Vec3) -> (usize, Vec2) {
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let (face, s, t, major) = if ax >= ay && ax >= az {
+        if x > 0.0 { (0, -z, -y, ax) } else { (1, z, -y, ax) }
+    } else if ay >= az {
+        if y > 0.0 { (2, x, z, ay) } else { (3, x, -z, ay) }
+    } else if z > 0.0 {
+        (4, x, -y, az)
+    } else {
+        (5, -x, -y, az)
+    };
+    (face, Vec2::new((s / major + 1.0) * 0.5, (t / major + 1.0) * 0.5))
+}
+
+// Hilos con los que se hornea; 0 = uno por núcleo
+static THREADS: AtomicUsize = AtomicUsize::new(0);
+
+// `None` vuelve a usar un hilo por núcleo
+pub fn set_threads(threads: Option<usize>) {
+    THREADS.store(threads.unwrap_or(0), Ordering::Relaxed);
+}
+
+// Evalúa `sample` en cada texel repartiendo las filas entre los hilos
+fn parallel_rows<T: Copy + Send + Default, F: Fn(usize, usize) -> T + Sync>(width: usize, height: usize, sample: F) -> Vec<T> {
+    let mut texels = vec![T::default(); width * height];
+    let threads = match THREADS.load(Ordering::Relaxed) {
+        0 => thread::available_parallelism().map_or(1, |count| count.get()),
+        threads => threads,
+    };
+    let rows_per_thread = height.div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        for (chunk, rows) in texels.chunks_mut(rows_per_thread * width).enumerate() {
+            let sample = &sample;
+            scope.spawn(move || {
+                for (i, texel) in rows.iter_mut().enumerate() {
+                    let y = chunk * rows_per_thread + i / width;
+                    *texel = sample(i % width, y);
+                }
+            });
+        }
+    });
+    texels
+}
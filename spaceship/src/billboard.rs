@@ -0,0 +1,34 @@
+use crate::vertex::Vertex;
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+
+// Cuadrado unitario en el plano XY (coordenadas entre -1 y 1) mirando hacia +Z
+pub fn create_billboard() -> Vec<Vertex> {
+    let normal = Vec3::new(0.0, 0.0, 1.0);
+    let corners = [
+        (Vec3::new(-1.0, -1.0, 0.0), Vec2::new(0.0, 1.0)),
+        (Vec3::new(1.0, -1.0, 0.0), Vec2::new(1.0, 1.0)),
+        (Vec3::new(1.0, 1.0, 0.0), Vec2::new(1.0, 0.0)),
+        (Vec3::new(-1.0, 1.0, 0.0), Vec2::new(0.0, 0.0)),
+    ];
+
+    [0, 1, 2, 0, 2, 3].iter()
+        .map(|&i| Vertex::new(corners[i].0, normal, corners[i].1))
+        .collect()
+}
+
+// Matriz de modelo que pone el billboard paralelo al plano de la imagen, centrado en
+// `center`. Así sus cuatro esquinas quedan a la misma profundidad y la interpolación
+// lineal en pantalla de las coordenadas locales no se deforma.
+pub fn billboard_matrix(center: Vec3, scale: f32, view_matrix: &Mat4) -> Mat4 {
+    // Las filas de la rotación de la vista son los ejes de la cámara en espacio mundo
+    let right = Vec3::new(view_matrix[(0, 0)], view_matrix[(0, 1)], view_matrix[(0, 2)]) * scale;
+    let up = Vec3::new(view_matrix[(1, 0)], view_matrix[(1, 1)], view_matrix[(1, 2)]) * scale;
+    let forward = Vec3::new(view_matrix[(2, 0)], view_matrix[(2, 1)], view_matrix[(2, 2)]) * scale;
+
+    Mat4::new(
+        right.x, up.x, forward.x, center.x,
+        right.y, up.y, forward.y, center.y,
+        right.z, up.z, forward.z, center.z,
+        0.0,     0.0,  0.0,       1.0,
+    )
+}
@@ -0,0 +1,229 @@
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Sistema Solar - Software Renderer
+
+Uso: spaceship [opciones]
+
+Opciones:
+  --width <px>         Ancho de la ventana (por defecto 800)
+  --height <px>        Alto de la ventana (por defecto 800)
+  --scale <factor>     Resolución de render relativa a la ventana, entre 0.1 y 4 (por defecto 1);
+                       el render no puede pasar de 8192 x 4096 píxeles en total
+  --stars <n>          Estrellas del fondo (por defecto 800)
+  --seed <n>           Semilla de las estrellas del fondo y de las partículas
+  --scene <archivo>    Archivo de escena TOML (por defecto sistema_solar.toml en los recursos)
+  --ship <modelo>      Modelo de la nave (OBJ, glTF o GLB); reemplaza el de la escena
+  --assets <carpeta>   Carpeta de recursos (por defecto $SPACESHIP_ASSETS o la carpeta assets)
+  --fps-cap <fps>      Cuadros por segundo como máximo; 0 = sin límite (por defecto 60)
+  --threads <n>        Hilos para el trabajo en paralelo (por defecto uno por núcleo)
+  --headless           Sin ventana ni teclado; necesita --frames
+  --frames <n>         Termina después de dibujar n cuadros
+  --screenshot <png>   Guarda el último cuadro dibujado en un PNG
+  -h, --help           Muestra esta ayuda";
+
+// Píxeles del framebuffer como máximo (ancho x alto después de --scale). Cada píxel lleva
+// color, profundidad y los buffers de los efectos: más que esto no entra en memoria
+const MAX_RENDER_PIXELS: usize = 8192 * 4096;
+
+// Opciones de la línea de comandos; los valores por defecto son los del programa sin opciones
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    pub scale: f32,
+    pub stars: usize,
+    pub seed: Option<u32>,
+    pub scene: Option<PathBuf>,
+    pub ship: Option<PathBuf>,
+    pub assets: Option<PathBuf>,
+    // `None` = sin límite
+    pub fps_cap: Option<u32>,
+    // `None` = uno por núcleo
+    pub threads: Option<usize>,
+    pub headless: bool,
+    pub frames: Option<u64>,
+    pub screenshot: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            width: 800,
+            height: 800,
+            scale: 1.0,
+            stars: 800,
+            seed: None,
+            scene: None,
+            ship: None,
+            assets: None,
+            fps_cap: Some(60),
+            threads: None,
+            headless: false,
+            frames: None,
+            screenshot: None,
+        }
+    }
+}
+
+impl Options {
+    // Tamaño del framebuffer: el de la ventana por la escala
+    pub fn framebuffer_size(&self) -> (usize, usize) {
+        let scaled = |size: usize| ((size as f32 * self.scale).round() as usize).max(1);
+        (scaled(self.width), scaled(self.height))
+    }
+}
+
+pub enum Command {
+    Run(Options),
+    Help,
+}
+
+#[derive(Debug)]
+pub struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Lee las opciones (sin el nombre del programa). Se aceptan `--opcion valor` y `--opcion=valor`.
+pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Command, CliError> {
+    let mut options = Options::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |expected: &str| -> Result<String, CliError> {
+            inline_value.clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError(format!("falta el valor de {} (se espera {})", flag, expected)))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--width" => options.width = number(&flag, &value("un ancho en píxeles")?, 1, 8192)?,
+            "--height" => options.height = number(&flag, &value("un alto en píxeles")?, 1, 8192)?,
+            "--scale" => {
+                let text = value("un factor")?;
+                options.scale = parse_value(&flag, &text, "un número entre 0.1 y 4")
+                    .and_then(|scale: f32| in_range(&flag, &text, scale, 0.1, 4.0))?;
+            }
+            "--stars" => options.stars = number(&flag, &value("una cantidad")?, 0, 1_000_000)?,
+            "--seed" => options.seed = Some(parse_value(&flag, &value("un entero")?, "un entero sin signo")?),
+            "--scene" => options.scene = Some(absolute(&value("un archivo")?)),
+            "--ship" => options.ship = Some(absolute(&value("un modelo")?)),
+            "--assets" => options.assets = Some(absolute(&value("una carpeta")?)),
+            "--fps-cap" => {
+                let fps = number(&flag, &value("cuadros por segundo")?, 0, 1000)?;
+                options.fps_cap = (fps > 0).then_some(fps);
+            }
+            "--threads" => options.threads = Some(number(&flag, &value("una cantidad de hilos")?, 1, 256)?),
+            "--headless" if inline_value.is_some() => return Err(CliError(format!("{} no lleva valor", flag))),
+            "--headless" => options.headless = true,
+            "--frames" => options.frames = Some(number(&flag, &value("una cantidad de cuadros")?, 1, 1_000_000_000)?),
+            "--screenshot" => options.screenshot = Some(absolute(&value("un archivo PNG")?)),
+            _ => return Err(CliError(format!("opción desconocida '{}'", arg))),
+        }
+    }
+
+    if options.headless && options.frames.is_none() {
+        return Err(CliError("--headless necesita --frames: sin ventana no hay forma de cerrar el programa".to_string()));
+    }
+    let (width, height) = options.framebuffer_size();
+    if width * height > MAX_RENDER_PIXELS {
+        return Err(CliError(format!(
+            "resolución de render {} x {} demasiado grande (máximo {} píxeles); reduzca --width, --height o --scale",
+            width, height, MAX_RENDER_PIXELS
+        )));
+    }
+    Ok(Command::Run(options))
+}
+
+fn parse_value<T: FromStr>(flag: &str, text: &str, expected: &str) -> Result<T, CliError> {
+    text.trim().parse().map_err(|_| CliError(format!("valor inválido para {}: '{}' (se espera {})", flag, text, expected)))
+}
+
+fn in_range<T: PartialOrd + fmt::Display>(flag: &str, text: &str, value: T, min: T, max: T) -> Result<T, CliError> {
+    if value >= min && value <= max {
+        Ok(value)
+    } else {
+        Err(CliError(format!("valor fuera de rango para {}: '{}' (debe estar entre {} y {})", flag, text, min, max)))
+    }
+}
+
+// Entero entre `min` y `max`
+fn number<T: FromStr + PartialOrd + fmt::Display>(flag: &str, text: &str, min: T, max: T) -> Result<T, CliError> {
+    let value = parse_value(flag, text, &format!("un entero entre {} y {}", min, max))?;
+    in_range(flag, text, value, min, max)
+}
+
+// Las rutas de la línea de comandos son relativas al directorio actual, no a la carpeta de recursos
+fn absolute(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    match env::current_dir() {
+        Ok(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str]) -> Result<Command, CliError> {
+        parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    // Mensaje de error de una línea de comandos que no debe aceptarse
+    fn error(args: &[&str]) -> String {
+        match run(args) {
+            Ok(_) => panic!("se esperaba un error para {:?}", args),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn valid_options() {
+        let Ok(Command::Run(options)) = run(&["--width=640", "--height", "360", "--scale", "0.5", "--headless", "--frames", "3"]) else {
+            panic!("las opciones son válidas");
+        };
+        assert_eq!(options.framebuffer_size(), (320, 180));
+        assert_eq!(options.frames, Some(3));
+        assert!(matches!(run(&["--fps-cap", "0", "-h"]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn unknown_flag() {
+        assert_eq!(error(&["--bogus"]), "opción desconocida '--bogus'");
+    }
+
+    #[test]
+    fn scale_out_of_range() {
+        let message = error(&["--scale", "9"]);
+        assert!(message.contains("fuera de rango para --scale"), "{}", message);
+        assert!(error(&["--scale=abc"]).contains("valor inválido para --scale"));
+    }
+
+    #[test]
+    fn missing_value() {
+        assert!(error(&["--width"]).contains("falta el valor de --width"));
+    }
+
+    #[test]
+    fn headless_needs_frames() {
+        assert!(error(&["--headless"]).contains("--headless necesita --frames"));
+        assert!(error(&["--headless=1", "--frames", "2"]).contains("--headless no lleva valor"));
+    }
+
+    #[test]
+    fn render_pixel_budget() {
+        assert!(error(&["--width", "8192", "--height", "8192", "--scale", "4"]).contains("demasiado grande"));
+    }
+}
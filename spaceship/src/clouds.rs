@@ -0,0 +1,125 @@
+use nalgebra_glm::Vec3;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::noise::fbm;
+use crate::fragment_shader::smoothstep;
+use crate::Uniforms;
+
+// Capa de nubes independiente: una esfera transparente algo mayor que el planeta
+#[derive(Clone, Copy)]
+pub struct CloudLayer {
+    // Altura sobre la superficie como fracción del radio del planeta
+    pub altitude: f32,
+    // Velocidad de rotación propia (radianes por segundo)
+    pub rotation_speed: f32,
+    // Fracción aproximada del cielo cubierta, entre 0 y 1
+    pub coverage: f32,
+    pub color: Vec3,
+    // Opacidad máxima de las nubes más densas
+    pub opacity: f32,
+    // Frecuencia del ruido sobre la esfera unitaria
+    pub scale: f32,
+    // Cuánto oscurece la sombra de una nube densa sobre la superficie
+    pub shadow_strength: f32,
+}
+
+impl CloudLayer {
+    pub fn earth_like() -> Self {
+        CloudLayer {
+            altitude: 0.04,
+            rotation_speed: 0.35,
+            coverage: 0.45,
+            color: Vec3::new(1.0, 1.0, 1.0),
+            opacity: 0.9,
+            scale: 3.0,
+            shadow_strength: 0.5,
+        }
+    }
+
+    // Neblina tenue y casi uniforme, como la del planeta helado
+    pub fn haze() -> Self {
+        CloudLayer {
+            altitude: 0.03,
+            rotation_speed: -0.15,
+            coverage: 0.7,
+            color: Vec3::new(0.8, 0.92, 1.0),
+            opacity: 0.35,
+            scale: 1.5,
+            shadow_strength: 0.15,
+        }
+    }
+
+    pub fn shell_scale(&self) -> f32 {
+        1.0 + self.altitude
+    }
+
+    // Densidad de nubes en un punto de la esfera unitaria de la capa (ya rotada), entre 0 y 1
+    pub fn coverage_at(&self, position: &Vec3, time: f32, octaves: u32) -> f32 {
+        // Desplazamiento lento del ruido para que las nubes cambien de forma, no sólo giren
+        let drift = Vec3::new(time * 0.02, time * 0.01, -time * 0.015);
+        let noise = (fbm(&(position * self.scale + drift), octaves) * 2.5).clamp(-1.0, 1.0);
+        let threshold = 1.0 - 2.0 * self.coverage;
+        smoothstep(threshold, threshold + 0.4, noise)
+    }
+}
+
+// Estado de la capa de nubes de un cuerpo para proyectar sombras sobre su superficie
+#[derive(Clone, Copy)]
+pub struct CloudShadow {
+    pub layer: CloudLayer,
+    pub rotation: f32,
+}
+
+pub fn clouds_shader(fragment: &Fragment, uniforms: &Uniforms, layer: &CloudLayer, vertex_position: &Vec3, vertex_normal: &Vec3) -> (Color, f32) {
+    let center = uniforms.model_matrix.column(3).xyz();
+    let radius = uniforms.model_matrix.column(0).xyz().magnitude();
+
+    // Desde fuera sólo se ve la cara frontal de la capa y desde dentro sólo la trasera
+    let view = fragment.world_position - uniforms.camera_position;
+    let camera_inside = (uniforms.camera_position - center).magnitude() < radius;
+    let front_face = (fragment.world_position - center).dot(&view) < 0.0;
+    if camera_inside == front_face {
+        return (Color::black(), 0.0);
+    }
+
+    let time = uniforms.time as f32 * 0.016;
+    let density = layer.coverage_at(&vertex_position.normalize(), time, 5);
+    if density <= 0.0 {
+        return (Color::black(), 0.0);
+    }
+
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let diffuse = vertex_normal.dot(&light_dir).max(0.0);
+    // Las nubes densas se ven un poco más oscuras por debajo
+    let intensity = (diffuse * (1.0 - density * 0.25) + 0.05).min(1.0);
+    let color = layer.color.component_mul(&uniforms.light_color) * intensity;
+
+    (Color::from_float(color.x, color.y, color.z), density * layer.opacity)
+}
+
+// Factor de luz (1 = sin sombra) en un punto de la superficie según las nubes entre él y el sol
+pub fn cloud_shadow_factor(fragment: &Fragment, uniforms: &Uniforms, shadow: &CloudShadow) -> f32 {
+    let center = uniforms.model_matrix.column(3).xyz();
+    let planet_radius = uniforms.model_matrix.column(0).xyz().magnitude();
+    let cloud_radius = planet_radius * shadow.layer.shell_scale();
+
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let offset = fragment.world_position - center;
+
+    // Salida del rayo hacia el sol por la esfera de nubes (el punto está dentro de ella)
+    let b = offset.dot(&light_dir);
+    let c = offset.dot(&offset) - cloud_radius * cloud_radius;
+    let t = -b + (b * b - c).max(0.0).sqrt();
+    let hit = (offset + light_dir * t) / cloud_radius;
+
+    // Llevar el punto al espacio de la capa, que gira sobre el eje Y
+    let (sin, cos) = shadow.rotation.sin_cos();
+    let local = Vec3::new(cos * hit.x - sin * hit.z, hit.y, sin * hit.x + cos * hit.z);
+
+    // Menos octavas que las nubes visibles: la sombra queda suave
+    let time = uniforms.time as f32 * 0.016;
+    let density = shadow.layer.coverage_at(&local, time, 3);
+    // En el lado nocturno no hay sol que proyecte sombras (y no deben tapar la luz propia)
+    let daylight = smoothstep(-0.1, 0.1, offset.normalize().dot(&light_dir));
+    1.0 - density * shadow.layer.opacity * shadow.layer.shadow_strength * daylight
+}
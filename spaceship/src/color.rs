@@ -1,4 +1,5 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Color {
@@ -31,9 +32,14 @@ impl Color {
     Color { r, g, b }
   }
 
-  pub fn to_hex(&self) -> u32 {
+  pub fn to_hex(self) -> u32 {
     ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
   }
+
+  // Componentes en [0, 1]
+  pub fn to_vec3(self) -> Vec3 {
+    Vec3::new(self.r as f32, self.g as f32, self.b as f32) / 255.0
+  }
 }
 
 use std::ops::Add;
@@ -64,6 +70,19 @@ impl Mul<f32> for Color {
   }
 }
 
+// Tinte por canal (por ejemplo, el color de la luz que ilumina una superficie)
+impl Mul<Vec3> for Color {
+  type Output = Color;
+
+  fn mul(self, tint: Vec3) -> Color {
+    Color {
+      r: (self.r as f32 * tint.x).clamp(0.0, 255.0) as u8,
+      g: (self.g as f32 * tint.y).clamp(0.0, 255.0) as u8,
+      b: (self.b as f32 * tint.z).clamp(0.0, 255.0) as u8,
+    }
+  }
+}
+
 impl fmt::Display for Color {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
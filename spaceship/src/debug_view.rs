@@ -0,0 +1,132 @@
+use nalgebra_glm::{Mat4, Vec3};
+use std::time::Duration;
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::framebuffer::Framebuffer;
+
+// Qué se dibuja en cada píxel: la imagen final o un dato interno del renderizador.
+// En los modos de depuración sólo se dibuja la geometría opaca.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DebugView {
+    Final,
+    // Profundidad del z-buffer: blanco cerca, negro lejos
+    Depth,
+    // Normales en espacio mundo (XYZ -> RGB)
+    Normals,
+    // Posición en coordenadas del objeto, repetida cada 2 unidades
+    Position,
+    Uv,
+    // Un color distinto por triángulo de cada malla
+    TriangleId,
+    // Tiempo del fragment shader por píxel: azul barato, rojo caro
+    ShaderCost,
+}
+
+impl DebugView {
+    pub fn next(self) -> Self {
+        match self {
+            DebugView::Final => DebugView::Depth,
+            DebugView::Depth => DebugView::Normals,
+            DebugView::Normals => DebugView::Position,
+            DebugView::Position => DebugView::Uv,
+            DebugView::Uv => DebugView::TriangleId,
+            DebugView::TriangleId => DebugView::ShaderCost,
+            DebugView::ShaderCost => DebugView::Final,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugView::Final => "Imagen final",
+            DebugView::Depth => "Profundidad (z-buffer)",
+            DebugView::Normals => "Normales",
+            DebugView::Position => "Posición del objeto",
+            DebugView::Uv => "Coordenadas UV",
+            DebugView::TriangleId => "Triángulos",
+            DebugView::ShaderCost => "Costo del shader",
+        }
+    }
+}
+
+// Cómo se dibujan las mallas: superficies sombreadas, sólo las aristas o ambas
+#[derive(Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    Shaded,
+    Wireframe,
+    ShadedWireframe,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Shaded => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::ShadedWireframe,
+            RenderMode::ShadedWireframe => RenderMode::Shaded,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderMode::Shaded => "Sombreado",
+            RenderMode::Wireframe => "Alambre",
+            RenderMode::ShadedWireframe => "Sombreado con alambre",
+        }
+    }
+}
+
+// Color de un fragmento en los modos que sólo dependen de sus datos interpolados
+pub fn attribute_color(view: DebugView, fragment: &Fragment, normal: &Vec3, position: &Vec3) -> Color {
+    let color = match view {
+        DebugView::Normals => normal * 0.5 + Vec3::repeat(0.5),
+        DebugView::Position => (position * 0.5 + Vec3::repeat(0.5)).map(|c| c.rem_euclid(1.0)),
+        DebugView::Uv => Vec3::new(fragment.tex_coords.x.rem_euclid(1.0), fragment.tex_coords.y.rem_euclid(1.0), 0.0),
+        _ => Vec3::zeros(),
+    };
+    Color::from_float(color.x, color.y, color.z)
+}
+
+pub fn triangle_id_color(id: usize) -> Color {
+    let mut h = (id as u32).wrapping_mul(0x9e37_79b9);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    // Evitar colores demasiado oscuros para distinguir los triángulos del fondo
+    Color::new((h >> 16) as u8 | 0x40, (h >> 8) as u8 | 0x40, h as u8 | 0x40)
+}
+
+// Mapa de calor en escala logarítmica: 50 ns o menos es azul y 5 µs o más es rojo
+pub fn shader_cost_color(elapsed: Duration) -> Color {
+    let nanos = elapsed.as_nanos().max(1) as f32;
+    let t = ((nanos / 50.0).ln() / 100.0_f32.ln()).clamp(0.0, 1.0);
+    let stops = [
+        Vec3::new(0.0, 0.0, 0.6),
+        Vec3::new(0.0, 0.8, 0.8),
+        Vec3::new(0.2, 0.9, 0.1),
+        Vec3::new(1.0, 0.9, 0.0),
+        Vec3::new(1.0, 0.1, 0.0),
+    ];
+    let scaled = t * (stops.len() - 1) as f32;
+    let index = (scaled as usize).min(stops.len() - 2);
+    let color = stops[index].lerp(&stops[index + 1], scaled - index as f32);
+    Color::from_float(color.x, color.y, color.z)
+}
+
+// Reemplaza la imagen por la profundidad del z-buffer. El z-buffer guarda la profundidad
+// NDC, que no es lineal; se convierte a distancia con la matriz de proyección y se muestra
+// en escala logarítmica entre los planos cercano y lejano.
+pub fn show_depth(framebuffer: &mut Framebuffer, projection_matrix: &Mat4) {
+    let a = projection_matrix[(2, 2)];
+    let b = projection_matrix[(2, 3)];
+    let near = b / (a - 1.0);
+    let far = b / (a + 1.0);
+
+    for (pixel, &depth) in framebuffer.buffer.iter_mut().zip(&framebuffer.zbuffer) {
+        *pixel = if depth.is_finite() {
+            let distance = b / (depth + a);
+            let t = ((distance / near).ln() / (far / near).ln()).clamp(0.0, 1.0);
+            Color::from_float(1.0 - t, 1.0 - t, 1.0 - t).to_hex()
+        } else {
+            0x000000
+        };
+    }
+}
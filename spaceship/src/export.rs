@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4};
+use crate::color::Color;
+use crate::fragment::Fragment;
+use crate::fragment_shader::{fragment_shader, ShaderType};
+use crate::obj::Material;
+use crate::shaders::{normal_matrix, vertex_shader};
+use crate::vertex::Vertex;
+use crate::Uniforms;
+
+// Malla indexada en espacio mundo, lista para escribirse en OBJ o PLY
+pub struct ExportMesh {
+    pub name: String,
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub texcoords: Vec<Vec2>,
+    // Color de cada vértice calculado con el shader del objeto, si se horneó
+    pub colors: Option<Vec<Color>>,
+    pub indices: Vec<u32>,
+    // Color difuso del material; en PLY, que no tiene materiales, colorea los vértices
+    pub diffuse_color: Color,
+}
+
+impl ExportMesh {
+    // Convierte una lista de triángulos (como la que reciben los shaders) a una malla
+    // indexada con la matriz de modelo aplicada. Los vértices repetidos se unen.
+    pub fn from_vertices(name: &str, vertices: &[Vertex], model_matrix: &Mat4, diffuse_color: Color) -> Self {
+        let normal_matrix = normal_matrix(model_matrix);
+        let mut mesh = ExportMesh {
+            name: name.to_string(),
+            positions: Vec::new(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            colors: None,
+            indices: Vec::with_capacity(vertices.len()),
+            diffuse_color,
+        };
+
+        let mut unique = HashMap::new();
+        for vertex in vertices {
+            let key: Vec<u32> = vertex.position.iter()
+                .chain(vertex.normal.iter())
+                .chain(vertex.tex_coords.iter())
+                .map(|value| value.to_bits())
+                .collect();
+            let index = *unique.entry(key).or_insert_with(|| {
+                let position = model_matrix * Vec4::new(vertex.position.x, vertex.position.y, vertex.position.z, 1.0);
+                let normal = normal_matrix * vertex.normal;
+                mesh.positions.push(position.xyz());
+                mesh.normals.push(if normal.magnitude() > 1e-12 { normal.normalize() } else { normal });
+                mesh.texcoords.push(vertex.tex_coords);
+                (mesh.positions.len() - 1) as u32
+            });
+            mesh.indices.push(index);
+        }
+        mesh
+    }
+
+    // Evalúa el fragment shader en cada vértice y guarda el resultado como color de vértice.
+    // `vertices` debe ser la misma lista con la que se creó la malla.
+    pub fn bake_colors(&mut self, vertices: &[Vertex], uniforms: &Uniforms, shader_type: &ShaderType) {
+        // La atenuación por distancia depende de la cámara, no de la superficie
+        let uniforms = Uniforms { depth_cue: None, ..uniforms.clone() };
+        let mut colors = vec![Color::black(); self.positions.len()];
+        for (vertex, &index) in vertices.iter().zip(&self.indices) {
+            let transformed = vertex_shader(vertex, &uniforms);
+            let mut fragment = Fragment::new(0.0, 0.0, vertex.color, 0.0);
+            fragment.tex_coords = vertex.tex_coords;
+            fragment.world_position = transformed.world_position;
+            fragment.tangent = transformed.transformed_tangent;
+            let normal = transformed.transformed_normal.normalize();
+            let (color, _) = fragment_shader(&fragment, &uniforms, shader_type, &vertex.position, &normal);
+            colors[index as usize] = color;
+        }
+        self.colors = Some(colors);
+    }
+}
+
+// Escena capturada durante un cuadro: cada objeto dibujado con su transformación
+pub struct SceneSnapshot {
+    pub meshes: Vec<ExportMesh>,
+    bake_colors: bool,
+}
+
+impl SceneSnapshot {
+    pub fn new(bake_colors: bool) -> Self {
+        SceneSnapshot { meshes: Vec::new(), bake_colors }
+    }
+
+    pub fn add(&mut self, name: &str, vertices: &[Vertex], uniforms: &Uniforms, shader_type: &ShaderType) {
+        // La corona es un cuadrado orientado hacia la cámara; fuera del renderer no tiene sentido
+        if matches!(shader_type, ShaderType::Corona(_)) || vertices.is_empty() {
+            return;
+        }
+        // Los vértices de la nave llevan el color de su material; el resto se sombrea en el shader
+        let diffuse_color = match shader_type {
+            ShaderType::Spaceship => vertices[0].color,
+            _ => Material::default().diffuse_color,
+        };
+        let mut mesh = ExportMesh::from_vertices(name, vertices, &uniforms.model_matrix, diffuse_color);
+        if self.bake_colors {
+            mesh.bake_colors(vertices, uniforms, shader_type);
+        }
+        self.meshes.push(mesh);
+    }
+
+    // Escribe la escena en `<base>.obj` (con `<base>.mtl`) y `<base>.ply`
+    pub fn save(&self, base: &Path) -> io::Result<()> {
+        if let Some(dir) = base.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_obj(&base.with_extension("obj"), &self.meshes)?;
+        write_ply(&base.with_extension("ply"), &self.meshes)
+    }
+}
+
+// Escribe las mallas como objetos de un OBJ y sus materiales en un MTL con el mismo nombre.
+// Los colores horneados van tras cada vértice (v x y z r g b), como los exporta Blender.
+pub fn write_obj(path: &Path, meshes: &[ExportMesh]) -> io::Result<()> {
+    let mtl_path = path.with_extension("mtl");
+    let mut mtl = BufWriter::new(File::create(&mtl_path)?);
+    let mut obj = BufWriter::new(File::create(path)?);
+
+    writeln!(obj, "# Sistema Solar - Software Renderer")?;
+    if let Some(name) = mtl_path.file_name() {
+        writeln!(obj, "mtllib {}", name.to_string_lossy())?;
+    }
+
+    // Los índices del OBJ empiezan en 1 y son globales al archivo
+    let mut offset = 1;
+    for (i, mesh) in meshes.iter().enumerate() {
+        // El índice evita que dos nombres que sólo difieren en los espacios se junten
+        let material = format!("{}_{}", i, mesh.name.replace(char::is_whitespace, "_"));
+        let diffuse = mesh.diffuse_color.to_vec3();
+        writeln!(mtl, "newmtl {}", material)?;
+        writeln!(mtl, "Kd {:.6} {:.6} {:.6}", diffuse.x, diffuse.y, diffuse.z)?;
+        writeln!(mtl, "illum 1")?;
+        writeln!(mtl)?;
+
+        writeln!(obj, "o {}", material)?;
+        for (i, position) in mesh.positions.iter().enumerate() {
+            write!(obj, "v {:.6} {:.6} {:.6}", position.x, position.y, position.z)?;
+            if let Some(colors) = &mesh.colors {
+                let color = colors[i].to_vec3();
+                write!(obj, " {:.4} {:.4} {:.4}", color.x, color.y, color.z)?;
+            }
+            writeln!(obj)?;
+        }
+        // El OBJ tiene el origen de las UV abajo; el motor, arriba
+        for texcoord in &mesh.texcoords {
+            writeln!(obj, "vt {:.6} {:.6}", texcoord.x, 1.0 - texcoord.y)?;
+        }
+        for normal in &mesh.normals {
+            writeln!(obj, "vn {:.6} {:.6} {:.6}", normal.x, normal.y, normal.z)?;
+        }
+        writeln!(obj, "usemtl {}", material)?;
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [tri[0] + offset, tri[1] + offset, tri[2] + offset];
+            writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        offset += mesh.positions.len() as u32;
+    }
+
+    mtl.flush()?;
+    obj.flush()
+}
+
+// Escribe todas las mallas juntas en un PLY binario con normales, UV y color por vértice
+// (el horneado, o el del material si no se horneó)
+pub fn write_ply(path: &Path, meshes: &[ExportMesh]) -> io::Result<()> {
+    let mut ply = BufWriter::new(File::create(path)?);
+    let vertex_count: usize = meshes.iter().map(|mesh| mesh.positions.len()).sum();
+    let face_count: usize = meshes.iter().map(|mesh| mesh.indices.len() / 3).sum();
+
+    writeln!(ply, "ply")?;
+    writeln!(ply, "format binary_little_endian 1.0")?;
+    writeln!(ply, "comment Sistema Solar - Software Renderer")?;
+    writeln!(ply, "element vertex {}", vertex_count)?;
+    for property in ["x", "y", "z", "nx", "ny", "nz", "s", "t"] {
+        writeln!(ply, "property float {}", property)?;
+    }
+    for property in ["red", "green", "blue"] {
+        writeln!(ply, "property uchar {}", property)?;
+    }
+    writeln!(ply, "element face {}", face_count)?;
+    writeln!(ply, "property list uchar uint vertex_indices")?;
+    writeln!(ply, "end_header")?;
+
+    for mesh in meshes {
+        for i in 0..mesh.positions.len() {
+            let (position, normal, texcoord) = (mesh.positions[i], mesh.normals[i], mesh.texcoords[i]);
+            // Igual que en el OBJ, la t del PLY crece hacia arriba
+            for value in [position.x, position.y, position.z, normal.x, normal.y, normal.z, texcoord.x, 1.0 - texcoord.y] {
+                ply.write_all(&value.to_le_bytes())?;
+            }
+            let color = mesh.colors.as_ref().map_or(mesh.diffuse_color, |colors| colors[i]).to_vec3() * 255.0;
+            ply.write_all(&[color.x.round() as u8, color.y.round() as u8, color.z.round() as u8])?;
+        }
+    }
+
+    let mut offset = 0;
+    for mesh in meshes {
+        for tri in mesh.indices.chunks_exact(3) {
+            ply.write_all(&[3])?;
+            for &index in tri {
+                ply.write_all(&(index + offset).to_le_bytes())?;
+            }
+        }
+        offset += mesh.positions.len() as u32;
+    }
+
+    ply.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::ImportOptions;
+    use crate::obj::{Obj, TextureCache};
+    use crate::sphere::create_sphere;
+
+    // Una esfera escrita con `write_obj` se vuelve a leer con el mismo número de triángulos
+    // y la misma caja envolvente (con la matriz de modelo aplicada)
+    #[test]
+    fn obj_round_trip() {
+        let dir = std::env::temp_dir().join(format!("spaceship_export_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("esfera.obj");
+
+        let vertices = create_sphere(2.0, 9, 17);
+        let model_matrix = nalgebra_glm::translation(&Vec3::new(1.0, 0.0, 0.0));
+        let mesh = ExportMesh::from_vertices("Esfera de prueba", &vertices, &model_matrix, Color::new(200, 100, 50));
+        write_obj(&path, &[mesh]).unwrap();
+
+        let obj = Obj::load(&path.to_string_lossy(), &ImportOptions::default(), &mut TextureCache::new()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let report = obj.report();
+        assert_eq!(report.meshes, 1);
+        assert_eq!(report.materials, vec!["0_Esfera_de_prueba".to_string()]);
+        // Los triángulos de los polos no tienen área y el importador los descarta
+        assert_eq!(report.triangles + report.degenerate_triangles, vertices.len() / 3);
+        assert!(report.triangles > 0);
+        assert!((report.bounds.min - Vec3::new(-1.0, -2.0, -2.0)).abs().max() < 1e-4, "{:?}", report.bounds.min);
+        assert!((report.bounds.max - Vec3::new(3.0, 2.0, 2.0)).abs().max() < 1e-4, "{:?}", report.bounds.max);
+    }
+}
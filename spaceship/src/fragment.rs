@@ -1,10 +1,16 @@
-use nalgebra_glm::Vec2;
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 pub struct Fragment {
     pub position: Vec2,
     pub color: Color,
+    pub alpha: f32,
     pub depth: f32,
+    pub tex_coords: Vec2,
+    // Área en espacio UV que cubre el píxel, usada para elegir el nivel de mipmap
+    pub uv_footprint: f32,
+    pub world_position: Vec3,
+    pub tangent: Vec4,
 }
 
 impl Fragment {
@@ -12,7 +18,12 @@ impl Fragment {
         Fragment {
             position: Vec2::new(x, y),
             color,
+            alpha: 1.0,
             depth,
+            tex_coords: Vec2::new(0.0, 0.0),
+            uv_footprint: 0.0,
+            world_position: Vec3::new(0.0, 0.0, 0.0),
+            tangent: Vec4::new(0.0, 0.0, 0.0, 1.0),
         }
     }
 }
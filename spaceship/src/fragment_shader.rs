@@ -1,116 +1,214 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::Vec3;
 use crate::fragment::Fragment;
-use crate::vertex::Vertex;
 use crate::color::Color;
 use crate::Uniforms;
+use crate::noise::{fbm, perlin};
+use crate::atmosphere::{Atmosphere, atmosphere_shader};
+use crate::clouds::{CloudLayer, clouds_shader, cloud_shadow_factor};
+use crate::star::{Star, star_surface_shader, corona_shader};
+use crate::ring::{RingSystem, rings_shader, ring_shadow_factor};
+use crate::planet::{PlanetParams, procedural_planet_shader};
+use crate::bake::Surface;
 
+#[derive(Clone)]
 pub enum ShaderType {
-    Sun,
-    RockyPlanet,
+    Sun(Star),
+    Desert,
     GasGiant,
     EarthLike,
     IcePlanet,
+    Volcanic,
     Moon,
-    Rings,
+    // Planeta generado a partir de una semilla y sus parámetros
+    Procedural(PlanetParams),
+    Rings(RingSystem),
     Spaceship,  // Nuevo shader para la nave
+    Atmosphere(Atmosphere),
+    Clouds(CloudLayer),
+    // Corona de una estrella, dibujada sobre un billboard
+    Corona(Star),
 }
 
-pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: &ShaderType, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
-    match shader_type {
-        ShaderType::Sun => sun_shader(fragment, uniforms, vertex_position, vertex_normal),
-        ShaderType::RockyPlanet => rocky_planet_shader(fragment, uniforms, vertex_position, vertex_normal),
+// Cómo se combinan los fragmentos de un shader con lo que ya hay en el framebuffer
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Opaque,
+    // Mezcla con el fondo según la opacidad del fragmento, sin escribir profundidad
+    Alpha,
+    // Suma la luz emitida/dispersada sin escribir profundidad
+    Additive,
+}
+
+impl ShaderType {
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            ShaderType::Atmosphere(_) | ShaderType::Corona(_) => BlendMode::Additive,
+            ShaderType::Clouds(_) | ShaderType::Rings(_) => BlendMode::Alpha,
+            _ => BlendMode::Opaque,
+        }
+    }
+}
+
+// Atenuación suave del brillo con la distancia a la cámara, para distinguir los cuerpos
+// lejanos de los cercanos
+#[derive(Clone, Copy)]
+pub struct DepthCue {
+    // Distancia a la que empieza la atenuación y a la que llega al máximo
+    pub start: f32,
+    pub end: f32,
+    // Brillo que queda a partir de `end`
+    pub min_brightness: f32,
+}
+
+impl DepthCue {
+    pub fn subtle() -> Self {
+        DepthCue { start: 10.0, end: 60.0, min_brightness: 0.55 }
+    }
+
+    fn factor(&self, distance: f32) -> f32 {
+        1.0 - (1.0 - self.min_brightness) * smoothstep(self.start, self.end, distance)
+    }
+}
+
+// Luz mínima del lado nocturno de los planetas
+const AMBIENT: f32 = 0.06;
+
+// Devuelve el color del fragmento y su opacidad (1.0 para los shaders opacos)
+pub fn fragment_shader(fragment: &Fragment, uniforms: &Uniforms, shader_type: &ShaderType, vertex_position: &Vec3, vertex_normal: &Vec3) -> (Color, f32) {
+    let (color, alpha) = shade(fragment, uniforms, shader_type, vertex_position, vertex_normal);
+
+    // Sólo se atenúan los planetas y sus capas; el sol y la nave conservan su brillo
+    let cued = !matches!(shader_type, ShaderType::Sun(_) | ShaderType::Corona(_) | ShaderType::Spaceship);
+    match &uniforms.depth_cue {
+        Some(cue) if cued => {
+            let distance = (fragment.world_position - uniforms.camera_position).magnitude();
+            (color * cue.factor(distance), alpha)
+        }
+        _ => (color, alpha),
+    }
+}
+
+fn shade(fragment: &Fragment, uniforms: &Uniforms, shader_type: &ShaderType, vertex_position: &Vec3, vertex_normal: &Vec3) -> (Color, f32) {
+    let color = match shader_type {
+        ShaderType::Sun(star) => star_surface_shader(fragment, uniforms, star, vertex_position, vertex_normal),
+        ShaderType::Desert => desert_shader(fragment, uniforms, vertex_position, vertex_normal),
         ShaderType::GasGiant => gas_giant_shader(fragment, uniforms, vertex_position, vertex_normal),
         ShaderType::EarthLike => earth_like_shader(fragment, uniforms, vertex_position, vertex_normal),
         ShaderType::IcePlanet => ice_planet_shader(fragment, uniforms, vertex_position, vertex_normal),
+        ShaderType::Volcanic => volcanic_shader(fragment, uniforms, vertex_position, vertex_normal),
         ShaderType::Moon => moon_shader(fragment, uniforms, vertex_position, vertex_normal),
-        ShaderType::Rings => rings_shader(fragment, uniforms, vertex_position, vertex_normal),
-        ShaderType::Spaceship => spaceship_shader(fragment, vertex_normal),
-    }
+        ShaderType::Procedural(params) => procedural_planet_shader(fragment, uniforms, params, vertex_position, vertex_normal),
+        ShaderType::Spaceship => spaceship_shader(fragment, uniforms, vertex_normal),
+        ShaderType::Atmosphere(atmosphere) => return atmosphere_shader(fragment, uniforms, atmosphere),
+        ShaderType::Clouds(layer) => return clouds_shader(fragment, uniforms, layer, vertex_position, vertex_normal),
+        ShaderType::Corona(star) => return corona_shader(uniforms, star, vertex_position),
+        ShaderType::Rings(rings) => return rings_shader(fragment, uniforms, rings, vertex_position),
+    };
+
+    // Sombras de la capa de nubes y de los anillos del cuerpo, si los tiene
+    let color = match &uniforms.cloud_shadow {
+        Some(shadow) => color * cloud_shadow_factor(fragment, uniforms, shadow),
+        None => color,
+    };
+    let color = match &uniforms.ring_shadow {
+        Some(shadow) => color * ring_shadow_factor(fragment, uniforms, shadow),
+        None => color,
+    };
+    (color, 1.0)
 }
 
-fn sun_shader(_fragment: &Fragment, uniforms: &Uniforms, _vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
-    let time = uniforms.time as f32 * 0.5;
-    
-    let zoom = 100.0;
-    let x = vertex_normal.x;
-    let y = vertex_normal.y;
-    
-    // 3 capas de ruido para superficie solar dinámica
-    let noise_value1 = (x * zoom + time).sin() * (y * zoom + time).cos();
-    let noise_value2 = ((x + 0.5) * zoom * 1.5 - time * 0.8).sin() * ((y + 0.5) * zoom * 1.5 + time * 0.8).cos();
-    let noise_value3 = ((x * 0.7 + y * 0.3) * zoom * 0.8 + time * 1.2).sin();
-    
-    let combined_noise = (noise_value1 + noise_value2 + noise_value3) / 3.0;
-    let brightness = (combined_noise + 1.0) * 0.5;
-    
-    let core_white = Color::new(255, 255, 240);
-    let bright_yellow = Color::new(255, 220, 100);
-    let yellow = Color::new(255, 180, 50);
-    let orange = Color::new(255, 100, 0);
-    let deep_red = Color::new(200, 30, 0);
-    
-    if brightness > 0.85 {
-        core_white
-    } else if brightness > 0.7 {
-        bright_yellow
-    } else if brightness > 0.5 {
-        yellow
-    } else if brightness > 0.3 {
-        orange
-    } else {
-        deep_red
-    }
+// Mundo desértico tipo Marte: 5 capas (llanuras, mares de dunas, cañones, casquetes polares
+// y tormentas de polvo que recorren el planeta)
+fn desert_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+    let time = uniforms.time as f32 * 0.016;
+    let surface = surface_at(fragment, uniforms, vertex_position, desert_surface);
+    let normal = surface_normal(uniforms, &surface);
+    let light_dir = light_direction(fragment, uniforms);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, sun_facing(vertex_normal, &light_dir));
+    
+    // El polvo en suspensión tapa el relieve y se ilumina sin las sombras del terreno
+    let storm = dust_storm(&vertex_position.normalize(), time);
+    let dust = Color::new(200, 140, 90);
+    let flat_intensity = surface_lighting(uniforms, vertex_normal, &light_dir, sun_facing(vertex_normal, &light_dir));
+    mix(lit_albedo(&surface.albedo, &intensity), dust * flat_intensity, storm * 0.8)
 }
 
-fn rocky_planet_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
-    let time = uniforms.time as f32 * 0.05;
-    
-    let zoom = 40.0;
-    let x = vertex_position.x;
-    let y = vertex_position.y;
-    let z = vertex_position.z;
-    
-    // 4 capas de ruido para textura rocosa
-    let noise1 = (x * zoom + time * 0.1).sin() * (y * zoom).cos() * (z * zoom).sin();
-    let noise2 = ((x + 0.3) * zoom * 0.7).cos() * ((y + 0.7) * zoom * 0.9).sin();
-    let noise3 = ((z + 0.5) * zoom * 1.3).sin() * ((x - 0.2) * zoom * 0.5).cos();
-    let noise4 = ((x * y * zoom * 0.3).sin() + (z * y * zoom * 0.4).cos()) * 0.5;
-    
-    let combined = noise1 * 0.4 + noise2 * 0.3 + noise3 * 0.2 + noise4 * 0.1;
-    
-    let dark_rock = Color::new(40, 30, 25);
-    let medium_rock = Color::new(80, 60, 45);
-    let light_rock = Color::new(120, 90, 65);
-    let sandy = Color::new(160, 130, 90);
-    let light_sand = Color::new(190, 160, 120);
-    
-    let crater_pattern = ((x * 25.0).sin() * (z * 25.0).cos() + 1.0) * 0.5;
-    
-    let base_color = if combined > 0.5 {
-        light_sand
-    } else if combined > 0.2 {
-        sandy
-    } else if combined > -0.1 {
-        light_rock
-    } else if combined > -0.4 {
-        medium_rock
+// Parte estática del desierto: llanuras, dunas, cañones y casquetes con su relieve
+pub(crate) fn desert_surface(position: &Vec3) -> Surface {
+    let direction = position.normalize();
+    
+    let plains = fbm(&(direction * 3.0), 4);
+    let dunes = dune_ridges(&direction);
+    let canyons = canyon_depth(&direction);
+    
+    let dark_rust = Color::new(105, 45, 25);
+    let rust = Color::new(155, 75, 40);
+    let ochre = Color::new(190, 115, 65);
+    let sand = Color::new(215, 160, 105);
+    let pale_sand = Color::new(230, 195, 145);
+    let canyon_floor = Color::new(80, 35, 22);
+    let ice_cap = Color::new(235, 235, 240);
+    
+    let mut base_color = if plains > 0.2 {
+        pale_sand
+    } else if plains > 0.05 {
+        sand
+    } else if plains > -0.1 {
+        ochre
+    } else if plains > -0.25 {
+        rust
     } else {
-        dark_rock
+        dark_rust
     };
-    
-    let light_dir = Vec3::new(0.5, 0.8, -0.5).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.7 + 0.3;
-    
-    let crater_shadow = if crater_pattern > 0.85 { 0.7 } else { 1.0 };
-    
-    Color::new(
-        (base_color.to_hex() >> 16 & 0xFF) as u8,
-        (base_color.to_hex() >> 8 & 0xFF) as u8,
-        (base_color.to_hex() & 0xFF) as u8,
-    ) * intensity * crater_shadow
+    // Las crestas de las dunas tienen arena más clara que los valles entre ellas
+    base_color = base_color * (0.9 + dunes * 0.2);
+    base_color = mix(base_color, dark_rust, smoothstep(0.1, 0.4, canyons));
+    base_color = mix(base_color, canyon_floor, smoothstep(0.5, 0.9, canyons));
+    
+    // Casquetes de hielo pequeños con bordes irregulares
+    let cap_edge = 0.9 + fbm(&(direction * 8.0), 3) * 0.08;
+    base_color = mix(base_color, ice_cap, smoothstep(cap_edge, cap_edge + 0.02, direction.y.abs()));
+    
+    // Las dunas levantan la superficie y los cañones la hunden
+    let height = |p: &Vec3| {
+        let d = p.normalize();
+        fbm(&(d * 3.0), 4) * 0.2 + dune_ridges(&d) * 0.04 - canyon_depth(&d) * 0.3
+    };
+    Surface {
+        albedo: base_color.to_vec3(),
+        normal: bump_direction(position, height, 0.03),
+        mask: 0.0,
+    }
 }
 
-fn gas_giant_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+// Crestas de dunas perpendiculares al viento dominante, onduladas por ruido (1 en la cresta)
+fn dune_ridges(direction: &Vec3) -> f32 {
+    let warp = fbm(&(direction * 6.0), 2) * 8.0;
+    let phase = (direction.x * 0.8 + direction.z * 0.6) * 40.0 + warp;
+    let ridge = (1.0 - phase.sin().abs()).powi(3);
+    // Sólo hay dunas en los mares de arena
+    let erg = smoothstep(-0.05, 0.15, fbm(&(direction * 2.0 + Vec3::new(4.3, 1.1, 7.9)), 3));
+    ridge * erg
+}
+
+// Profundidad de los cañones: crestas de ruido con el dominio deformado (1 en el fondo)
+fn canyon_depth(direction: &Vec3) -> f32 {
+    let warp = fbm(&(direction * 2.0 + Vec3::new(2.5, 8.1, 0.6)), 3) * 2.0;
+    let ridge = 1.0 - perlin(&(direction * 3.0 + Vec3::repeat(warp))).abs() * 2.0;
+    // Sólo algunas regiones tienen sistemas de cañones
+    let region = smoothstep(0.0, 0.15, fbm(&(direction * 1.5 + Vec3::new(9.4, 3.3, 6.1)), 2));
+    smoothstep(0.9, 0.98, ridge) * region
+}
+
+// Densidad de las tormentas de polvo: frentes que giran alrededor del planeta y cambian de forma
+fn dust_storm(direction: &Vec3, time: f32) -> f32 {
+    let (sin, cos) = (time * 0.05).sin_cos();
+    let drifted = Vec3::new(cos * direction.x - sin * direction.z, direction.y, sin * direction.x + cos * direction.z);
+    let storm = fbm(&(drifted * 2.5 + Vec3::new(0.0, time * 0.02, time * 0.03)), 4);
+    smoothstep(0.12, 0.3, storm)
+}
+
+fn gas_giant_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
     let time = uniforms.time as f32 * 0.2;
     
     let y = vertex_position.y;
@@ -143,9 +241,7 @@ fn gas_giant_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
     
     let combined_bands = (bands * 0.6 + bands2 * 0.4 + turbulence).clamp(0.0, 1.0);
     
-    let base_color = if storm > 0.3 {
-        white
-    } else if combined_bands > 0.75 {
+    let base_color = if storm > 0.3 || combined_bands > 0.75 {
         white
     } else if combined_bands > 0.6 {
         pale_blue
@@ -159,32 +255,21 @@ fn gas_giant_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
     
     let swirl_factor = swirl * 0.15;
     
-    let light_dir = Vec3::new(0.3, 0.7, -0.6).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.6 + 0.4;
+    let light_dir = light_direction(fragment, uniforms);
+    let intensity = surface_lighting(uniforms, vertex_normal, &light_dir, sun_facing(vertex_normal, &light_dir));
     
     Color::new(
         (base_color.to_hex() >> 16 & 0xFF) as u8,
         (base_color.to_hex() >> 8 & 0xFF) as u8,
         (base_color.to_hex() & 0xFF) as u8,
-    ) * (intensity + storm + swirl_factor)
+    ) * (intensity * (1.0 + storm + swirl_factor))
 }
 
-fn earth_like_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+fn earth_like_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
     let time = uniforms.time as f32 * 0.03;
     
-    let zoom = 30.0;
-    let x = vertex_position.x;
-    let y = vertex_position.y;
-    let z = vertex_position.z;
-    
-    // 4 capas: continentes, océanos, vegetación, nubes
-    let continent_noise = (x * zoom * 0.5 + time * 0.1).sin() * (z * zoom * 0.5).cos() * (y * zoom * 0.3).sin();
-    let ocean_depth = ((x + 0.5) * zoom * 0.8).cos() * ((z - 0.3) * zoom * 0.7).sin();
-    let vegetation = ((x * zoom * 1.5).sin() + (z * zoom * 1.3).cos() + (y * zoom * 1.1).sin()) * 0.3;
-    let clouds = ((x * zoom * 2.0 + time * 2.0).sin() * (y * zoom * 2.5 - time * 1.5).cos() * (z * zoom * 2.2 + time).sin());
-    let cloud_factor = (clouds + 1.0) * 0.5;
-    
-    let combined = continent_noise + ocean_depth * 0.3 + vegetation * 0.2;
+    // 3 capas: continentes, océanos, vegetación (las nubes son una capa aparte)
+    let combined = earth_terrain(vertex_position, time);
     
     let deep_ocean = Color::new(10, 40, 100);
     let ocean = Color::new(20, 80, 150);
@@ -193,7 +278,6 @@ fn earth_like_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
     let land = Color::new(60, 120, 40);
     let mountain = Color::new(100, 90, 70);
     let snow = Color::new(240, 240, 255);
-    let cloud_white = Color::new(255, 255, 255);
     
     let base_color = if combined > 0.6 {
         snow
@@ -211,29 +295,79 @@ fn earth_like_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
         deep_ocean
     };
     
-    let light_dir = Vec3::new(0.5, 0.7, -0.5).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.7 + 0.3;
-    
-    // Mezcla de nubes con superficie
-    let final_color = if cloud_factor > 0.7 {
-        let blend = (cloud_factor - 0.7) / 0.3;
-        Color::new(
-            ((base_color.to_hex() >> 16 & 0xFF) as f32 * (1.0 - blend) + (cloud_white.to_hex() >> 16 & 0xFF) as f32 * blend) as u8,
-            ((base_color.to_hex() >> 8 & 0xFF) as f32 * (1.0 - blend) + (cloud_white.to_hex() >> 8 & 0xFF) as f32 * blend) as u8,
-            ((base_color.to_hex() & 0xFF) as f32 * (1.0 - blend) + (cloud_white.to_hex() & 0xFF) as f32 * blend) as u8,
-        )
+    // Sólo la tierra firme tiene relieve; el mar se mantiene liso
+    let height = |p: &Vec3| earth_terrain(p, time).max(0.0);
+    let normal = bump_normal(uniforms, vertex_position, height, 0.03);
+    let light_dir = light_direction(fragment, uniforms);
+    let facing = sun_facing(vertex_normal, &light_dir);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, facing);
+    
+    // Luces de ciudades en tierra firme (sin nieve), lejos de los polos
+    let on_land = if combined > 0.0 && combined <= 0.6 { 1.0 } else { 0.0 };
+    let emission = city_lights(vertex_position) * on_land * night_side(facing);
+    let city_glow = Color::new(255, 200, 110);
+    
+    // Destello del sol sobre el agua, con la normal ondulada por olas de ruido
+    let glint = if combined <= 0.0 {
+        let waves = |p: &Vec3| perlin(&(p * 20.0 + Vec3::new(time * 2.0, 0.0, time * 1.3)));
+        let water_normal = bump_normal(uniforms, vertex_position, waves, 0.006);
+        ocean_specular(fragment, uniforms, &water_normal, &light_dir) * smoothstep(0.0, 0.1, facing)
     } else {
-        base_color
+        0.0
     };
+    let sun_glint = Color::new(255, 245, 225);
     
     Color::new(
-        (final_color.to_hex() >> 16 & 0xFF) as u8,
-        (final_color.to_hex() >> 8 & 0xFF) as u8,
-        (final_color.to_hex() & 0xFF) as u8,
-    ) * intensity
+        (base_color.to_hex() >> 16 & 0xFF) as u8,
+        (base_color.to_hex() >> 8 & 0xFF) as u8,
+        (base_color.to_hex() & 0xFF) as u8,
+    ) * intensity + city_glow * emission + sun_glint * (uniforms.light_color * glint)
+}
+
+// Reflejo especular del océano: Blinn-Phong con el vector de vista real y Fresnel de
+// Schlick, así el brillo crece hacia el borde del disco donde se mira el agua de canto
+pub(crate) fn ocean_specular(fragment: &Fragment, uniforms: &Uniforms, normal: &Vec3, light_dir: &Vec3) -> f32 {
+    let to_camera = uniforms.camera_position - fragment.world_position;
+    if to_camera.magnitude() < 1e-4 {
+        return 0.0;
+    }
+    let view_dir = to_camera.normalize();
+    let half_vector = (light_dir + view_dir).normalize();
+    
+    let shininess = 120.0;
+    let specular = normal.dot(&half_vector).max(0.0).powf(shininess) * (shininess + 8.0) / 8.0;
+    
+    // Reflectancia del agua a incidencia normal ~2%
+    let f0 = 0.02;
+    let cos_view = normal.dot(&view_dir).clamp(0.0, 1.0);
+    let fresnel = f0 + (1.0 - f0) * (1.0 - cos_view).powi(5);
+    
+    (specular * fresnel * normal.dot(light_dir).max(0.0) * 4.0).min(1.0)
+}
+
+// Intensidad de las luces urbanas: manchas grandes de zonas pobladas salpicadas de ciudades
+fn city_lights(position: &Vec3) -> f32 {
+    let direction = position.normalize();
+    let latitude_fade = 1.0 - smoothstep(0.55, 0.75, direction.y.abs());
+    let regions = smoothstep(0.0, 0.2, fbm(&(direction * 4.0), 3));
+    let towns = smoothstep(0.15, 0.45, perlin(&(direction * 60.0)));
+    regions * towns * latitude_fade
+}
+
+fn earth_terrain(position: &Vec3, time: f32) -> f32 {
+    let zoom = 30.0;
+    let x = position.x;
+    let y = position.y;
+    let z = position.z;
+    
+    let continent_noise = (x * zoom * 0.5 + time * 0.1).sin() * (z * zoom * 0.5).cos() * (y * zoom * 0.3).sin();
+    let ocean_depth = ((x + 0.5) * zoom * 0.8).cos() * ((z - 0.3) * zoom * 0.7).sin();
+    let vegetation = ((x * zoom * 1.5).sin() + (z * zoom * 1.3).cos() + (y * zoom * 1.1).sin()) * 0.3;
+    
+    continent_noise + ocean_depth * 0.3 + vegetation * 0.2
 }
 
-fn ice_planet_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+fn ice_planet_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
     let time = uniforms.time as f32 * 0.15;
     
     let zoom = 45.0;
@@ -243,8 +377,8 @@ fn ice_planet_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
     
     // 5 capas: cristales de hielo, grietas, escarcha, auroras polares, niebla
     let ice_crystals = (x * zoom * 1.5 + time * 0.5).sin() * (z * zoom * 1.3 - time * 0.3).cos();
-    let cracks = ((x * zoom * 4.0).sin() * (z * zoom * 3.5).cos() + (y * zoom * 3.8).sin()).abs();
-    let frozen_waves = ((y * zoom * 2.0).sin() + (x * zoom * 1.8).cos()) * 0.5;
+    let cracks = ice_cracks(vertex_position);
+    let frozen_waves = ice_waves(vertex_position);
     let frost = (x * zoom * 3.0).cos() * (z * zoom * 2.5).sin() * (y * zoom * 2.8).cos();
     
     // Efecto de auroras en los polos
@@ -297,8 +431,11 @@ fn ice_planet_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
         );
     }
     
-    let light_dir = Vec3::new(0.4, 0.8, -0.4).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.6 + 0.4;
+    // Las grietas son surcos hundidos y las olas congeladas ondulan la superficie
+    let height = |p: &Vec3| smoothstep(0.0, 0.3, ice_cracks(p)) * 0.3 + ice_waves(p) * 0.2;
+    let normal = bump_normal(uniforms, vertex_position, height, 0.008);
+    let light_dir = light_direction(fragment, uniforms);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, sun_facing(vertex_normal, &light_dir));
     
     let ice_reflection = if brightness > 0.7 { 1.2 } else { 1.0 };
     
@@ -309,20 +446,145 @@ fn ice_planet_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position:
     ) * intensity * ice_reflection
 }
 
-fn moon_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
-    let _time = uniforms.time as f32 * 0.02;
+fn ice_cracks(position: &Vec3) -> f32 {
+    let zoom = 45.0;
+    ((position.x * zoom * 4.0).sin() * (position.z * zoom * 3.5).cos() + (position.y * zoom * 3.8).sin()).abs()
+}
+
+fn ice_waves(position: &Vec3) -> f32 {
+    let zoom = 45.0;
+    ((position.y * zoom * 2.0).sin() + (position.x * zoom * 1.8).cos()) * 0.5
+}
+
+// Mundo volcánico: 5 capas (corteza de basalto, grietas, coladas de lava que avanzan, ciclo
+// de enfriamiento de la corteza y columnas de ceniza de los volcanes activos)
+fn volcanic_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+    let time = uniforms.time as f32 * 0.016;
+    let direction = vertex_position.normalize();
+    let terrain = surface_at(fragment, uniforms, vertex_position, volcanic_surface);
+    let base_color = Color::from_float(terrain.albedo.x, terrain.albedo.y, terrain.albedo.z);
+    let cracks = terrain.mask;
+    
+    let flows = lava_flows(&direction, time);
+    // Cada zona de las coladas se enfría y vuelve a brotar con su propio ciclo
+    let age = ((perlin(&(direction * 2.5)) + 1.0) * 3.0 + time * 0.04).fract();
+    let heat = (flows * (1.0 - age)).max(cracks * 0.8);
+    
+    // La temperatura decide el color: lava amarilla, naranja, roja oscura y corteza que se enfría
+    let hot_core = Color::new(255, 210, 90);
+    let lava = Color::new(255, 90, 20);
+    let dark_red = Color::new(140, 35, 15);
+    let cooling = Color::new(70, 32, 24);
+    let surface = if heat > 0.75 {
+        hot_core
+    } else if heat > 0.5 {
+        lava
+    } else if heat > 0.3 {
+        dark_red
+    } else if heat > 0.12 {
+        cooling
+    } else {
+        base_color
+    };
     
-    let zoom = 50.0;
-    let x = vertex_position.x;
-    let y = vertex_position.y;
-    let z = vertex_position.z;
+    // Las coladas rellenan el terreno y lo alisan
+    let smoothed = direction.lerp(&terrain.normal, 1.0 - flows * 0.7).normalize();
+    let normal = (uniforms.normal_matrix * smoothed).normalize();
+    let light_dir = light_direction(fragment, uniforms);
+    let facing = sun_facing(vertex_normal, &light_dir);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, facing);
     
-    let noise1 = (x * zoom).sin() * (y * zoom).cos() * (z * zoom).sin();
-    let noise2 = ((x + 0.3) * zoom * 0.7).cos() * ((y + 0.7) * zoom * 0.9).sin();
-    let noise3 = ((z + 0.5) * zoom * 1.3).sin() * ((x - 0.2) * zoom * 0.5).cos();
-    let noise4 = ((x * y * zoom * 0.3).sin() + (z * y * zoom * 0.4).cos()) * 0.5;
+    // Ceniza de las erupciones: tapa el suelo y el brillo de la lava
+    let plumes = ash_plumes(&direction, time);
+    let ash_cloud = Color::new(85, 80, 76);
+    let lit = mix(surface * intensity, ash_cloud * intensity, plumes * 0.85);
     
-    let combined = noise1 * 0.4 + noise2 * 0.3 + noise3 * 0.2 + noise4 * 0.1;
+    // De día la lava se ve por su color; de noche brilla y palpita lentamente
+    let pulse = 0.8 + 0.2 * (time * 1.5 + perlin(&(direction * 3.0)) * 6.0).sin();
+    let lava_glow = Color::new(255, 70, 10);
+    let glow = lava_glow * heat + hot_core * smoothstep(0.7, 1.0, heat);
+    
+    lit + glow * (pulse * night_side(facing) * (1.0 - plumes * 0.7))
+}
+
+// Parte estática del mundo volcánico: corteza de basalto y ceniza con sus grietas hundidas.
+// La máscara guarda las grietas, que brillan con la lava.
+pub(crate) fn volcanic_surface(position: &Vec3) -> Surface {
+    let direction = position.normalize();
+    
+    // Corteza de basalto oscuro con manchas de ceniza
+    let crust = fbm(&(direction * 5.0), 4);
+    let basalt = Color::new(35, 28, 26);
+    let ash = Color::new(75, 66, 60);
+    let base_color = if crust > 0.1 { ash } else { basalt };
+    
+    let height = |p: &Vec3| {
+        let d = p.normalize();
+        fbm(&(d * 5.0), 4) * 0.3 - lava_cracks(&d) * 0.2
+    };
+    Surface {
+        albedo: base_color.to_vec3(),
+        normal: bump_direction(position, height, 0.03),
+        mask: lava_cracks(&direction),
+    }
+}
+
+// Red de grietas a partir de las crestas del ruido (1 en el centro de la grieta, 0 fuera)
+fn lava_cracks(direction: &Vec3) -> f32 {
+    let ridge = 1.0 - perlin(&(direction * 6.0)).abs() * 2.0;
+    let fine = 1.0 - perlin(&(direction * 14.0 + Vec3::new(3.1, 1.7, 5.3))).abs() * 2.0;
+    smoothstep(0.85, 0.97, ridge) * 0.8 + smoothstep(0.9, 0.98, fine) * 0.4
+}
+
+// Coladas de lava: canales sinuosos por los que avanzan pulsos de material caliente
+fn lava_flows(direction: &Vec3, time: f32) -> f32 {
+    let warp = Vec3::new(
+        fbm(&(direction * 3.0), 2),
+        fbm(&(direction * 3.0 + Vec3::new(5.2, 1.3, 2.8)), 2),
+        fbm(&(direction * 3.0 + Vec3::new(1.7, 9.2, 3.4)), 2),
+    );
+    let channel = 1.0 - perlin(&(direction * 4.0 + warp * 1.5)).abs() * 2.0;
+    let channels = smoothstep(0.78, 0.96, channel);
+    // Bultos de ruido que se desplazan a lo largo de los canales
+    let surge = perlin(&(direction * 12.0 + warp * 3.0 - Vec3::new(0.0, time * 0.15, 0.0))) * 0.5 + 0.5;
+    channels * (0.6 + 0.4 * surge)
+}
+
+// Volcanes activos (direcciones sobre la esfera unitaria)
+const VOLCANOES: [(f32, f32, f32); 3] = [(0.5, 0.6, 0.62), (-0.7, 0.1, 0.7), (0.2, -0.5, -0.84)];
+
+// Densidad de ceniza: columnas que salen de cada volcán, se estiran con el viento y
+// crecen o se calman con cada erupción
+fn ash_plumes(direction: &Vec3, time: f32) -> f32 {
+    let mut ash: f32 = 0.0;
+    for (i, &(x, y, z)) in VOLCANOES.iter().enumerate() {
+        let vent = Vec3::new(x, y, z).normalize();
+        // El viento sopla hacia el este, tangente a la superficie
+        let wind = Vec3::new(0.0, 1.0, 0.0).cross(&vent).normalize();
+        let offset = direction - vent;
+        let along = offset.dot(&wind);
+        let across = (offset - wind * along).magnitude();
+        // La columna se alarga a favor del viento y apenas en contra
+        let stretch = if along > 0.0 { 0.35 } else { 0.1 };
+        let shape = (-(across / 0.15).powi(2) - (along / stretch).powi(2)).exp();
+        let eruption = 0.6 + 0.4 * (time * 0.3 + i as f32 * 2.1).sin();
+        let turbulence = (0.5 + fbm(&(direction * 10.0 - wind * time * 0.1), 3) * 2.5).clamp(0.0, 1.0);
+        ash = ash.max(shape * eruption * turbulence);
+    }
+    ash.min(1.0)
+}
+
+fn moon_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+    let surface = surface_at(fragment, uniforms, vertex_position, moon_surface);
+    let normal = surface_normal(uniforms, &surface);
+    let light_dir = light_direction(fragment, uniforms);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, sun_facing(vertex_normal, &light_dir));
+    lit_albedo(&surface.albedo, &intensity)
+}
+
+// Parte estática de la luna: regolito de grises con cráteres
+pub(crate) fn moon_surface(position: &Vec3) -> Surface {
+    let combined = moon_terrain(position);
     
     let dark_gray = Color::new(40, 40, 45);
     let medium_gray = Color::new(70, 70, 75);
@@ -330,8 +592,6 @@ fn moon_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3
     let pale_gray = Color::new(130, 130, 135);
     let bright_gray = Color::new(160, 160, 165);
     
-    let crater_pattern = ((x * 30.0).sin() * (z * 30.0).cos() + 1.0) * 0.5;
-    
     let base_color = if combined > 0.5 {
         bright_gray
     } else if combined > 0.2 {
@@ -344,77 +604,161 @@ fn moon_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3
         dark_gray
     };
     
-    let light_dir = Vec3::new(0.5, 0.7, -0.5).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.7 + 0.3;
-    
-    let crater_shadow = if crater_pattern > 0.85 { 0.6 } else { 1.0 };
-    
-    Color::new(
-        (base_color.to_hex() >> 16 & 0xFF) as u8,
-        (base_color.to_hex() >> 8 & 0xFF) as u8,
-        (base_color.to_hex() & 0xFF) as u8,
-    ) * intensity * crater_shadow
+    let height = |p: &Vec3| moon_terrain(p) * 0.1 + craters(p, 30.0);
+    Surface {
+        albedo: base_color.to_vec3(),
+        normal: bump_direction(position, height, 0.02),
+        mask: 0.0,
+    }
 }
 
-fn rings_shader(_fragment: &Fragment, uniforms: &Uniforms, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
-    let time = uniforms.time as f32 * 0.1;
-    
-    let distance = (vertex_position.x.powi(2) + vertex_position.z.powi(2)).sqrt();
-    
-    // 4 capas de anillos: bandas principales, bandas secundarias, partículas, densidad
-    let bands = ((distance * 50.0).sin() + 1.0) * 0.5;
-    let bands2 = ((distance * 80.0 + time).cos() + 1.0) * 0.5;
-    let particles = ((vertex_position.x * 100.0).sin() * (vertex_position.z * 100.0).cos() + 1.0) * 0.5;
-    let density = ((distance * 30.0).cos() + 1.0) * 0.5;
-    
-    let combined = bands * 0.4 + bands2 * 0.3 + particles * 0.2 + density * 0.1;
+fn moon_terrain(position: &Vec3) -> f32 {
+    let zoom = 50.0;
+    let x = position.x;
+    let y = position.y;
+    let z = position.z;
     
-    let dark_ring = Color::new(100, 90, 80);
-    let medium_ring = Color::new(150, 140, 120);
-    let light_ring = Color::new(200, 190, 170);
-    let bright_ring = Color::new(230, 220, 200);
-    let ice_ring = Color::new(240, 235, 220);
+    let noise1 = (x * zoom).sin() * (y * zoom).cos() * (z * zoom).sin();
+    let noise2 = ((x + 0.3) * zoom * 0.7).cos() * ((y + 0.7) * zoom * 0.9).sin();
+    let noise3 = ((z + 0.5) * zoom * 1.3).sin() * ((x - 0.2) * zoom * 0.5).cos();
+    let noise4 = ((x * y * zoom * 0.3).sin() + (z * y * zoom * 0.4).cos()) * 0.5;
     
-    let base_color = if combined > 0.8 {
-        ice_ring
-    } else if combined > 0.6 {
-        bright_ring
-    } else if combined > 0.4 {
-        light_ring
-    } else if combined > 0.25 {
-        medium_ring
-    } else {
-        dark_ring
+    noise1 * 0.4 + noise2 * 0.3 + noise3 * 0.2 + noise4 * 0.1
+}
+
+// Shader simple para la nave - usa sus colores originales con iluminación básica
+fn spaceship_shader(fragment: &Fragment, uniforms: &Uniforms, vertex_normal: &Vec3) -> Color {
+    // Usar la textura difusa (map_Kd) si el material la tiene, si no el color del vértice
+    let base_color = match &uniforms.diffuse_texture {
+        Some(texture) => texture.sample_color(fragment.tex_coords, fragment.uv_footprint),
+        None => fragment.color,
     };
     
-    // Espacios vacíos entre anillos 
-    let gap = if distance > 1.3 && distance < 1.4 {
-        0.2
-    } else if distance > 1.7 && distance < 1.75 {
-        0.3
-    } else {
-        1.0
+    let normal = match &uniforms.normal_texture {
+        Some(texture) => normal_map(fragment, texture.sample(fragment.tex_coords, fragment.uv_footprint), vertex_normal),
+        None => *vertex_normal,
     };
     
-    let light_dir = Vec3::new(0.3, 0.7, -0.6).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.0) * 0.5 + 0.5;
-    
-    Color::new(
-        (base_color.to_hex() >> 16 & 0xFF) as u8,
-        (base_color.to_hex() >> 8 & 0xFF) as u8,
-        (base_color.to_hex() & 0xFF) as u8,
-    ) * intensity * gap
+    // Iluminación direccional desde el sol
+    let light_dir = light_direction(fragment, uniforms);
+    let intensity = uniforms.light_color * (normal.dot(&light_dir).max(0.2) * 0.8) + Vec3::repeat(0.2);
+    
+    // Brillo especular de los materiales PBR: más concentrado cuanto menos rugoso y teñido
+    // del color base en los metales. Con rugosidad 1 (materiales MTL) no hay brillo.
+    let smoothness = 1.0 - uniforms.roughness.clamp(0.0, 1.0);
+    let view_dir = (uniforms.camera_position - fragment.world_position).normalize();
+    let half_dir = (light_dir + view_dir).normalize();
+    let shininess = 2.0 + 254.0 * smoothness * smoothness;
+    let highlight = normal.dot(&half_dir).max(0.0).powf(shininess) * smoothness * smoothness;
+    let specular_color = Vec3::repeat(0.04).lerp(&base_color.to_vec3(), uniforms.metallic);
+    let specular = uniforms.light_color.component_mul(&specular_color) * highlight;
+    
+    // Aplicar intensidad de luz al color base; los metales tienen menos reflejo difuso
+    base_color * (intensity * (1.0 - 0.5 * uniforms.metallic)) + Color::from_float(specular.x, specular.y, specular.z)
 }
 
-// Shader simple para la nave - usa sus colores originales con iluminación básica
-fn spaceship_shader(fragment: &Fragment, vertex_normal: &Vec3) -> Color {
-    // Usar el color del vértice 
-    let base_color = fragment.color.clone();
+// Dirección (normalizada) desde el fragmento hacia el sol, en espacio mundo
+pub(crate) fn light_direction(fragment: &Fragment, uniforms: &Uniforms) -> Vec3 {
+    let to_light = uniforms.light_position - fragment.world_position;
+    if to_light.magnitude() < 1e-4 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        to_light.normalize()
+    }
+}
+
+// Cuánto mira la superficie hacia el sol según la normal geométrica, sin relieve:
+// 1 con el sol en el cenit, 0 en el terminador y negativo en el lado nocturno
+pub(crate) fn sun_facing(vertex_normal: &Vec3, light_dir: &Vec3) -> f32 {
+    vertex_normal.normalize().dot(light_dir)
+}
+
+// Luz difusa del sol (con su color e intensidad) usando el detalle de la normal perturbada.
+// El lado nocturno se apaga con la normal geométrica, así el relieve no ilumina montañas
+// más allá del terminador.
+pub(crate) fn surface_lighting(uniforms: &Uniforms, normal: &Vec3, light_dir: &Vec3, facing: f32) -> Vec3 {
+    let day = smoothstep(-0.05, 0.15, facing);
+    Vec3::repeat(AMBIENT) + uniforms.light_color * ((1.0 - AMBIENT) * normal.dot(light_dir).max(0.0) * day)
+}
+
+// Peso de la emisión propia (ciudades, lava): nula de día y completa pasado el terminador
+fn night_side(facing: f32) -> f32 {
+    1.0 - smoothstep(-0.25, 0.05, facing)
+}
+
+// Interpolación lineal entre dos colores
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    a * (1.0 - t) + b * t
+}
+
+// Normal de una esfera procedural inclinada según el gradiente del campo de alturas.
+// El gradiente se calcula con diferencias finitas sobre el plano tangente en espacio
+// modelo y el resultado se lleva a espacio mundo con la matriz de normales.
+pub(crate) fn bump_normal<F: Fn(&Vec3) -> f32>(uniforms: &Uniforms, position: &Vec3, height: F, strength: f32) -> Vec3 {
+    (uniforms.normal_matrix * bump_direction(position, height, strength)).normalize()
+}
+
+// Normal con relieve en espacio modelo (la parte de `bump_normal` que se puede hornear)
+pub(crate) fn bump_direction<F: Fn(&Vec3) -> f32>(position: &Vec3, height: F, strength: f32) -> Vec3 {
+    let normal = position.normalize();
+    let reference = if normal.y.abs() < 0.99 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = reference.cross(&normal).normalize();
+    let bitangent = normal.cross(&tangent);
+    
+    let epsilon = 1e-3;
+    let h0 = height(position);
+    let dh_dt = (height(&(position + tangent * epsilon)) - h0) / epsilon;
+    let dh_db = (height(&(position + bitangent * epsilon)) - h0) / epsilon;
+    
+    (normal - (tangent * dh_dt + bitangent * dh_db) * strength).normalize()
+}
+
+// Parte estática de la superficie: de la textura horneada del cuerpo si la tiene, si no
+// se calcula en el momento
+pub(crate) fn surface_at<F: Fn(&Vec3) -> Surface>(fragment: &Fragment, uniforms: &Uniforms, position: &Vec3, evaluate: F) -> Surface {
+    match &uniforms.baked_surface {
+        Some(baked) => baked.sample(position, fragment.uv_footprint),
+        None => evaluate(position),
+    }
+}
+
+// Normal de la superficie con relieve en espacio mundo
+pub(crate) fn surface_normal(uniforms: &Uniforms, surface: &Surface) -> Vec3 {
+    (uniforms.normal_matrix * surface.normal).normalize()
+}
+
+// Albedo iluminado por la luz que llega a la superficie
+pub(crate) fn lit_albedo(albedo: &Vec3, intensity: &Vec3) -> Color {
+    let lit = albedo.component_mul(intensity);
+    Color::from_float(lit.x, lit.y, lit.z)
+}
+
+// Normal desde un mapa de normales en espacio tangente (valores RGB en [0, 1])
+fn normal_map(fragment: &Fragment, texel: Vec3, vertex_normal: &Vec3) -> Vec3 {
+    let tangent = fragment.tangent.xyz();
+    // Sin UV no hay tangentes y el mapa no se puede orientar
+    if tangent.magnitude() < 1e-4 {
+        return *vertex_normal;
+    }
     
-    // Iluminación simple direccional
-    let light_dir = Vec3::new(0.5, 0.7, -0.3).normalize();
-    let intensity = vertex_normal.dot(&light_dir).max(0.2) * 0.8 + 0.2; 
+    let normal = vertex_normal.normalize();
+    let tangent = (tangent - normal * normal.dot(&tangent)).normalize();
+    let bitangent = normal.cross(&tangent) * fragment.tangent.w.signum();
     
-    // Aplicar intensidad de luz al color base
-    base_color * intensity
+    let sample = texel * 2.0 - Vec3::new(1.0, 1.0, 1.0);
+    (tangent * sample.x + bitangent * sample.y + normal * sample.z).normalize()
+}
+
+// Cráteres como depresiones circulares con borde levemente elevado
+fn craters(position: &Vec3, frequency: f32) -> f32 {
+    let crater_pattern = ((position.x * frequency).sin() * (position.z * frequency).cos() + 1.0) * 0.5;
+    let floor = smoothstep(0.8, 0.95, crater_pattern);
+    let rim = smoothstep(0.7, 0.8, crater_pattern) - floor;
+    rim * 0.1 - floor * 0.3
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
 }
@@ -1,8 +1,16 @@
+use std::path::Path;
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::texture::{write_png, TextureError};
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
+    // Fracción de la luz del fondo que atraviesa las capas translúcidas (anillos, nubes)
+    // dibujadas sobre cada píxel; 1 = nada encima
+    pub transmittance: Vec<f32>,
     background_color: u32,
     current_color: u32,
 }
@@ -14,11 +22,18 @@ impl Framebuffer {
             height,
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            transmittance: vec![1.0; width * height],
             background_color: 0x000000,
             current_color: 0xFFFFFF,
         }
     }
 
+    // Guarda el contenido actual como PNG RGB
+    pub fn save_png(&self, path: &Path) -> Result<(), TextureError> {
+        let texels: Vec<Vec3> = self.buffer.iter().map(|&pixel| Color::from_hex(pixel).to_vec3()).collect();
+        write_png(path, self.width, self.height, &texels)
+    }
+
     pub fn clear(&mut self) {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
@@ -26,6 +41,9 @@ impl Framebuffer {
         for depth in self.zbuffer.iter_mut() {
             *depth = f32::INFINITY;
         }
+        for transmittance in self.transmittance.iter_mut() {
+            *transmittance = 1.0;
+        }
     }
 
     pub fn point(&mut self, x: usize, y: usize, depth: f32) {
@@ -38,6 +56,40 @@ impl Framebuffer {
         }
     }
 
+    // Suma un color al píxel si pasa la prueba de profundidad, sin escribir el z-buffer
+    pub fn add_point(&mut self, x: usize, y: usize, depth: f32, color: Color) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                self.buffer[index] = (Color::from_hex(self.buffer[index]) + color).to_hex();
+            }
+        }
+    }
+
+    // Mezcla un color con el píxel según su opacidad si pasa la prueba de profundidad
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, color: Color, alpha: f32) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.zbuffer[index] > depth {
+                let alpha = alpha.clamp(0.0, 1.0);
+                let background = Color::from_hex(self.buffer[index]);
+                self.buffer[index] = (background * (1.0 - alpha) + color * alpha).to_hex();
+                self.transmittance[index] *= 1.0 - alpha;
+            }
+        }
+    }
+
+    // Dibuja un píxel de una arista sin escribir el z-buffer. `bias` acerca la línea a la
+    // cámara para que gane frente a la superficie del mismo triángulo.
+    pub fn line_point(&mut self, x: usize, y: usize, depth: f32, bias: f32, color: Color) {
+        if x < self.width && y < self.height {
+            let index = y * self.width + x;
+            if self.zbuffer[index] >= depth - bias {
+                self.buffer[index] = color.to_hex();
+            }
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
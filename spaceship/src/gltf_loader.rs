@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use gltf::image::Format;
+use gltf::mesh::Mode;
+use gltf::texture::WrappingMode;
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use crate::color::Color;
+use crate::mesh::MeshData;
+use crate::obj::{Material, ObjError};
+use crate::texture::{Texture, WrapMode};
+
+// Mallas (con su nombre y material) de la escena de un glTF/GLB, ya en el espacio del
+// modelo: cada primitiva se lleva por la jerarquía de nodos. El resto de la importación
+// (ejes, procesamiento, pivote y tamaño) es la misma que la de los OBJ.
+pub struct GltfScene {
+    pub parts: Vec<(String, MeshData, Material)>,
+    pub material_names: Vec<String>,
+    pub warnings: Vec<ObjError>,
+    // El archivo y los buffers e imágenes externos que referencia
+    pub sources: Vec<PathBuf>,
+}
+
+pub fn load(filename: &str) -> Result<GltfScene, ObjError> {
+    let path = Path::new(filename);
+    let (document, buffers, images) = gltf::import(path).map_err(|err| match err {
+        gltf::Error::Io(err) if err.kind() == ErrorKind::NotFound => ObjError::NotFound(path.to_path_buf()),
+        gltf::Error::Io(err) => ObjError::Io(path.to_path_buf(), err),
+        err => ObjError::Gltf(err.to_string()),
+    })?;
+
+    let mut warnings = Vec::new();
+    if document.animations().len() > 0 {
+        warnings.push(ObjError::Unsupported(format!("{} animaciones; se usa la pose de reposo", document.animations().len())));
+    }
+    if document.skins().len() > 0 {
+        warnings.push(ObjError::Unsupported("esqueletos; las mallas quedan sin deformar".to_string()));
+    }
+
+    let mut textures = HashMap::new();
+    let materials: Vec<Material> = document.materials()
+        .map(|material| load_material(&material, &images, &mut textures, &mut warnings))
+        .collect();
+    let material_names = document.materials()
+        .enumerate()
+        .map(|(index, material)| material.name().map_or_else(|| format!("material {}", index), str::to_string))
+        .collect();
+
+    // Escena por defecto, o la primera si el archivo no indica ninguna
+    let Some(scene) = document.default_scene().or_else(|| document.scenes().next()) else {
+        return Err(ObjError::Gltf("el archivo no tiene escenas".to_string()));
+    };
+    // Los datos incrustados (GLB o URIs data:) no son archivos aparte
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let buffer_uris = document.buffers().filter_map(|buffer| match buffer.source() {
+        gltf::buffer::Source::Uri(uri) => Some(uri),
+        gltf::buffer::Source::Bin => None,
+    });
+    let image_uris = document.images().filter_map(|image| match image.source() {
+        gltf::image::Source::Uri { uri, .. } => Some(uri),
+        gltf::image::Source::View { .. } => None,
+    });
+    let mut sources = vec![path.to_path_buf()];
+    sources.extend(buffer_uris.chain(image_uris)
+        .filter(|uri| !uri.starts_with("data:"))
+        .map(|uri| base_dir.join(uri)));
+
+    let mut loader = SceneLoader { buffers: &buffers, materials: &materials, parts: Vec::new(), warnings };
+    for node in scene.nodes() {
+        loader.visit(&node, &Mat4::identity());
+    }
+
+    Ok(GltfScene { parts: loader.parts, material_names, warnings: loader.warnings, sources })
+}
+
+struct SceneLoader<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    materials: &'a [Material],
+    parts: Vec<(String, MeshData, Material)>,
+    warnings: Vec<ObjError>,
+}
+
+impl SceneLoader<'_> {
+    // Recorre el nodo y sus hijos acumulando la transformación padre * local
+    fn visit(&mut self, node: &gltf::Node, parent: &Mat4) {
+        let local: Vec<f32> = node.transform().matrix().iter().flatten().copied().collect();
+        let world = parent * Mat4::from_column_slice(&local);
+
+        if let Some(mesh) = node.mesh() {
+            let mesh_name = mesh.name().or(node.name()).map_or_else(|| format!("malla {}", mesh.index()), str::to_string);
+            for primitive in mesh.primitives() {
+                let name = format!("{} #{}", mesh_name, primitive.index());
+                if primitive.mode() != Mode::Triangles {
+                    self.warnings.push(ObjError::Unsupported(format!("primitiva '{}' en modo {:?}; sólo se cargan triángulos", name, primitive.mode())));
+                    continue;
+                }
+
+                let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+                let Some(positions) = reader.read_positions() else {
+                    self.warnings.push(ObjError::DegenerateMesh(name));
+                    continue;
+                };
+                let positions: Vec<Vec3> = positions.map(Vec3::from).collect();
+                let indices: Vec<u32> = reader.read_indices()
+                    .map_or_else(|| (0..positions.len() as u32).collect(), |indices| indices.into_u32().collect());
+                if let Some(&index) = indices.iter().find(|&&index| index as usize >= positions.len()) {
+                    self.warnings.push(ObjError::Unsupported(format!(
+                        "primitiva '{}' con el índice {} fuera de sus {} vértices; se ignora", name, index, positions.len()
+                    )));
+                    continue;
+                }
+                let mut data = MeshData {
+                    normals: reader.read_normals().map_or_else(Vec::new, |normals| normals.map(Vec3::from).collect()),
+                    // glTF ya tiene el origen de las UV arriba a la izquierda, como las texturas
+                    texcoords: reader.read_tex_coords(0)
+                        .map_or_else(Vec::new, |texcoords| texcoords.into_f32().map(Vec2::from).collect()),
+                    indices,
+                    positions,
+                };
+                data.transform_affine(&world);
+
+                let material = primitive.material().index()
+                    .and_then(|index| self.materials.get(index))
+                    .cloned()
+                    .unwrap_or_default();
+                self.parts.push((name, data, material));
+            }
+        }
+
+        for child in node.children() {
+            self.visit(&child, &world);
+        }
+    }
+}
+
+// Material PBR metálico-rugoso: color base (factor por textura), metalicidad, rugosidad y
+// mapa de normales. Las texturas metálico-rugosa, de oclusión y de emisión no se usan.
+fn load_material(
+    material: &gltf::Material,
+    images: &[gltf::image::Data],
+    textures: &mut HashMap<(usize, [u32; 3]), Arc<Texture>>,
+    warnings: &mut Vec<ObjError>,
+) -> Material {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+    let factor = Vec3::new(r, g, b);
+
+    let mut load = |texture: gltf::Texture, tint: Vec3| -> Option<Arc<Texture>> {
+        let key = (texture.index(), [tint.x.to_bits(), tint.y.to_bits(), tint.z.to_bits()]);
+        if let Some(texture) = textures.get(&key) {
+            return Some(texture.clone());
+        }
+        let image = &images[texture.source().index()];
+        let Some(texels) = image_texels(image) else {
+            warnings.push(ObjError::Unsupported(format!("textura {} en formato {:?}", texture.index(), image.format)));
+            return None;
+        };
+        let texels = texels.into_iter().map(|texel| texel.component_mul(&tint)).collect();
+        let wrap = match texture.sampler().wrap_s() {
+            WrappingMode::ClampToEdge => WrapMode::ClampToEdge,
+            WrappingMode::MirroredRepeat => WrapMode::MirroredRepeat,
+            WrappingMode::Repeat => WrapMode::Repeat,
+        };
+        let loaded = Arc::new(Texture::from_rgb(image.width as usize, image.height as usize, texels).with_wrap(wrap));
+        textures.insert(key, loaded.clone());
+        Some(loaded)
+    };
+
+    // El factor de color base multiplica la textura: se aplica una vez al cargarla
+    let diffuse_texture = pbr.base_color_texture().and_then(|info| load(info.texture(), factor));
+    let normal_texture = material.normal_texture().and_then(|normal| load(normal.texture(), Vec3::repeat(1.0)));
+
+    Material {
+        diffuse_color: Color::from_float(r, g, b),
+        diffuse_texture,
+        normal_texture,
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+    }
+}
+
+// Texels RGB en [0, 1] de una imagen decodificada de 8 o 16 bits por canal
+fn image_texels(image: &gltf::image::Data) -> Option<Vec<Vec3>> {
+    let (channels, bytes) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        _ => return None,
+    };
+    let channel = |texel: &[u8], index: usize| -> f32 {
+        match bytes {
+            1 => texel[index] as f32 / 255.0,
+            _ => u16::from_ne_bytes([texel[index * 2], texel[index * 2 + 1]]) as f32 / 65535.0,
+        }
+    };
+    Some(image.pixels.chunks_exact(channels * bytes).map(|texel| match channels {
+        // Gris (con o sin alfa): el mismo valor en los tres canales
+        1 | 2 => Vec3::repeat(channel(texel, 0)),
+        _ => Vec3::new(channel(texel, 0), channel(texel, 1), channel(texel, 2)),
+    }).collect())
+}
@@ -0,0 +1,128 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::fragment_shader::smoothstep;
+use crate::lens_flare::project_sun;
+use crate::Uniforms;
+
+// Los rayos se calculan a una fracción de la resolución: son suaves y el desenfoque
+// radial recorre muchas muestras por píxel
+const DOWNSAMPLE: usize = 2;
+// El brillo alrededor del disco del sol también emite rayos, hasta este múltiplo del radio
+const GLOW_RADIUS: f32 = 3.0;
+
+// Rayos crepusculares en espacio de pantalla: una máscara con la parte visible del sol
+// (según el z-buffer y las capas translúcidas) desenfocada radialmente desde el sol.
+// Los planetas, los anillos y la nave que tapan el sol dejan franjas de sombra en la luz.
+pub struct GodRays {
+    // Brillo total de los rayos
+    pub intensity: f32,
+    // Pérdida de cada muestra respecto de la anterior a lo largo del rayo (0-1); más alto = rayos más largos
+    pub decay: f32,
+    // Fracción del camino hacia el sol que recorren las muestras de cada píxel
+    pub density: f32,
+    pub samples: usize,
+    width: usize,
+    height: usize,
+    mask: Vec<f32>,
+    rays: Vec<f32>,
+}
+
+impl GodRays {
+    pub fn new(framebuffer_width: usize, framebuffer_height: usize) -> Self {
+        let width = framebuffer_width.div_ceil(DOWNSAMPLE);
+        let height = framebuffer_height.div_ceil(DOWNSAMPLE);
+        GodRays {
+            intensity: 0.7,
+            decay: 0.96,
+            density: 0.9,
+            samples: 40,
+            width,
+            height,
+            mask: vec![0.0; width * height],
+            rays: vec![0.0; width * height],
+        }
+    }
+
+    // Se llama con toda la escena ya dibujada, para que los buffers tengan los oclusores
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, sun_position: Vec3, sun_radius: f32, color: Vec3) {
+        let Some(sun) = project_sun(uniforms, sun_position, sun_radius) else {
+            return;
+        };
+        let center_x = sun.screen_position.x / DOWNSAMPLE as f32;
+        let center_y = sun.screen_position.y / DOWNSAMPLE as f32;
+        let emitter_radius = sun.screen_radius / DOWNSAMPLE as f32;
+
+        // Máscara de oclusión: brillo del sol en los píxeles donde se ve
+        let mut any_visible = false;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let dx = x as f32 + 0.5 - center_x;
+                let dy = y as f32 + 0.5 - center_y;
+                let distance = (dx * dx + dy * dy).sqrt() / emitter_radius.max(0.5);
+                let emission = 1.0 - smoothstep(1.0, GLOW_RADIUS, distance);
+                let value = if emission > 0.0 {
+                    let full_x = (x * DOWNSAMPLE).min(framebuffer.width - 1);
+                    let full_y = (y * DOWNSAMPLE).min(framebuffer.height - 1);
+                    emission * sun.visible_at(framebuffer, full_y * framebuffer.width + full_x)
+                } else {
+                    0.0
+                };
+                any_visible |= value > 0.0;
+                self.mask[y * self.width + x] = value;
+            }
+        }
+        if !any_visible {
+            return;
+        }
+
+        // Desenfoque radial: cada píxel suma la máscara a lo largo del camino hacia el sol
+        let weight = self.intensity / self.samples as f32;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut sample_x = x as f32 + 0.5;
+                let mut sample_y = y as f32 + 0.5;
+                let step_x = (center_x - sample_x) * self.density / self.samples as f32;
+                let step_y = (center_y - sample_y) * self.density / self.samples as f32;
+                let mut falloff = 1.0;
+                let mut light = 0.0;
+                for _ in 0..self.samples {
+                    if sample_x >= 0.0 && sample_y >= 0.0 && (sample_x as usize) < self.width && (sample_y as usize) < self.height {
+                        light += self.mask[sample_y as usize * self.width + sample_x as usize] * falloff;
+                    }
+                    falloff *= self.decay;
+                    sample_x += step_x;
+                    sample_y += step_y;
+                }
+                self.rays[y * self.width + x] = light * weight;
+            }
+        }
+
+        // Se suman a la imagen interpolando entre los píxeles de la resolución reducida
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let light = self.sample_rays((x as f32 + 0.5) / DOWNSAMPLE as f32 - 0.5, (y as f32 + 0.5) / DOWNSAMPLE as f32 - 0.5);
+                if light < 1.0 / 255.0 {
+                    continue;
+                }
+                let light = color * light;
+                framebuffer.add_point(x, y, f32::NEG_INFINITY, Color::from_float(light.x, light.y, light.z));
+            }
+        }
+    }
+
+    // Interpolación bilineal de los rayos en coordenadas de la resolución reducida
+    fn sample_rays(&self, x: f32, y: f32) -> f32 {
+        let x = x.clamp(0.0, (self.width - 1) as f32);
+        let y = y.clamp(0.0, (self.height - 1) as f32);
+        let x0 = x as usize;
+        let y0 = y as usize;
+        let x1 = (x0 + 1).min(self.width - 1);
+        let y1 = (y0 + 1).min(self.height - 1);
+        let tx = x - x0 as f32;
+        let ty = y - y0 as f32;
+        let top = self.rays[y0 * self.width + x0] * (1.0 - tx) + self.rays[y0 * self.width + x1] * tx;
+        let bottom = self.rays[y1 * self.width + x0] * (1.0 - tx) + self.rays[y1 * self.width + x1] * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
@@ -0,0 +1,207 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::Uniforms;
+
+// Puntos del disco del sol que se consultan en el z-buffer
+const OCCLUSION_SAMPLES: usize = 64;
+// Fracción de la diferencia hacia la visibilidad medida que se recorre en cada cuadro
+const VISIBILITY_SMOOTHING: f32 = 0.3;
+// Rayos principales de la estrella de brillo
+const STARBURST_RAYS: f32 = 6.0;
+
+// Reflejo interno de la lente que aparece a lo largo del eje sol -> centro de la pantalla
+struct Ghost {
+    // Posición sobre el eje: 1 es el sol, 0 el centro, negativo al otro lado del centro
+    offset: f32,
+    // Radio relativo al alto de la pantalla
+    size: f32,
+    tint: (f32, f32, f32),
+    // Anillo en vez de disco
+    ring: bool,
+}
+
+const GHOSTS: [Ghost; 6] = [
+    Ghost { offset: 0.55, size: 0.025, tint: (0.5, 0.9, 0.6), ring: false },
+    Ghost { offset: 0.3, size: 0.04, tint: (1.0, 0.7, 0.3), ring: false },
+    Ghost { offset: -0.15, size: 0.06, tint: (0.4, 0.6, 1.0), ring: false },
+    Ghost { offset: -0.4, size: 0.03, tint: (0.9, 0.5, 0.9), ring: false },
+    Ghost { offset: -0.7, size: 0.09, tint: (0.6, 0.8, 1.0), ring: true },
+    Ghost { offset: -1.1, size: 0.05, tint: (1.0, 0.6, 0.4), ring: false },
+];
+
+// Resultado de la consulta de oclusión: dónde está el sol en pantalla y cuánto se ve
+pub struct SunQuery {
+    pub screen_position: Vec2,
+    pub screen_radius: f32,
+    // Fracción del disco visible, entre 0 y 1
+    pub visibility: f32,
+}
+
+// Destello de la cámara al mirar al sol; guarda la visibilidad entre cuadros para que
+// aparezca y se apague suavemente
+pub struct LensFlare {
+    visibility: f32,
+}
+
+impl LensFlare {
+    pub fn new() -> Self {
+        LensFlare { visibility: 0.0 }
+    }
+
+    // Se llama con toda la escena ya dibujada, para que el z-buffer tenga los oclusores
+    pub fn render(&mut self, framebuffer: &mut Framebuffer, uniforms: &Uniforms, sun_position: Vec3, sun_radius: f32, color: Vec3) {
+        let query = sun_occlusion(framebuffer, uniforms, sun_position, sun_radius);
+        let target = query.as_ref().map_or(0.0, |query| query.visibility);
+        self.visibility += (target - self.visibility) * VISIBILITY_SMOOTHING;
+
+        let Some(query) = query else {
+            return;
+        };
+        if self.visibility < 0.005 {
+            return;
+        }
+
+        draw_starburst(framebuffer, &query, color * self.visibility);
+
+        let center = Vec2::new(framebuffer.width as f32, framebuffer.height as f32) * 0.5;
+        let axis = query.screen_position - center;
+        // Los fantasmas se debilitan cuando el sol se acerca al borde de la imagen
+        let edge = 1.0 - (axis.magnitude() / center.magnitude()).min(1.0);
+        for ghost in &GHOSTS {
+            let position = center + axis * ghost.offset;
+            let radius = ghost.size * framebuffer.height as f32;
+            let (r, g, b) = ghost.tint;
+            let tint = color.component_mul(&Vec3::new(r, g, b)) * (self.visibility * (0.3 + 0.7 * edge) * 0.25);
+            draw_ghost(framebuffer, position, radius, tint, ghost.ring);
+        }
+    }
+}
+
+// Disco del sol proyectado en pantalla
+pub struct SunProjection {
+    pub screen_position: Vec2,
+    pub screen_radius: f32,
+    // Profundidad NDC del punto del sol más cercano a la cámara
+    pub front_depth: f32,
+}
+
+impl SunProjection {
+    // Si el píxel deja ver el sol: nada opaco quedó dibujado delante de su cara visible.
+    // Devuelve la fracción de luz que llega, atenuada por las capas translúcidas.
+    pub fn visible_at(&self, framebuffer: &Framebuffer, index: usize) -> f32 {
+        // Margen relativo a la distancia, igual que la profundidad NDC
+        let tolerance = (1.0 - self.front_depth).max(0.0) * 0.01;
+        if framebuffer.zbuffer[index] >= self.front_depth - tolerance {
+            framebuffer.transmittance[index]
+        } else {
+            0.0
+        }
+    }
+}
+
+// Proyecta el sol a la pantalla; `None` si está detrás de la cámara o la cámara está dentro
+pub fn project_sun(uniforms: &Uniforms, sun_position: Vec3, sun_radius: f32) -> Option<SunProjection> {
+    let view_projection = uniforms.projection_matrix * uniforms.view_matrix;
+    let clip = view_projection * Vec4::new(sun_position.x, sun_position.y, sun_position.z, 1.0);
+    if clip.w <= 1e-3 {
+        return None;
+    }
+    let to_camera = uniforms.camera_position - sun_position;
+    if to_camera.magnitude() <= sun_radius {
+        return None;
+    }
+    let screen = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let front = sun_position + to_camera.normalize() * sun_radius;
+    let front_clip = view_projection * Vec4::new(front.x, front.y, front.z, 1.0);
+
+    Some(SunProjection {
+        screen_position: Vec2::new(screen.x, screen.y),
+        screen_radius: sun_radius * uniforms.projection_matrix[(1, 1)] * uniforms.viewport_matrix[(1, 1)].abs() / clip.w,
+        front_depth: front_clip.z / front_clip.w,
+    })
+}
+
+// Consulta de oclusión: compara con el z-buffer varios puntos repartidos sobre el disco
+// proyectado del sol. Los puntos fuera de la pantalla no se ven.
+pub fn sun_occlusion(framebuffer: &Framebuffer, uniforms: &Uniforms, sun_position: Vec3, sun_radius: f32) -> Option<SunQuery> {
+    let sun = project_sun(uniforms, sun_position, sun_radius)?;
+
+    // Espiral de Vogel: puntos repartidos de forma pareja sobre el disco
+    let golden_angle = PI * (3.0 - 5.0_f32.sqrt());
+    let mut visible = 0.0;
+    for i in 0..OCCLUSION_SAMPLES {
+        let r = ((i as f32 + 0.5) / OCCLUSION_SAMPLES as f32).sqrt() * sun.screen_radius * 0.95;
+        let angle = i as f32 * golden_angle;
+        let x = (sun.screen_position.x + r * angle.cos()).floor();
+        let y = (sun.screen_position.y + r * angle.sin()).floor();
+        if x < 0.0 || y < 0.0 || x >= framebuffer.width as f32 || y >= framebuffer.height as f32 {
+            continue;
+        }
+        visible += sun.visible_at(framebuffer, y as usize * framebuffer.width + x as usize);
+    }
+
+    Some(SunQuery {
+        screen_position: sun.screen_position,
+        screen_radius: sun.screen_radius,
+        visibility: visible / OCCLUSION_SAMPLES as f32,
+    })
+}
+
+// Estrella de brillo: halo alrededor del sol y rayos finos que se apagan hacia fuera
+fn draw_starburst(framebuffer: &mut Framebuffer, query: &SunQuery, color: Vec3) {
+    let radius = (query.screen_radius * 8.0).clamp(80.0, 320.0);
+    let center = query.screen_position;
+
+    for_each_pixel(framebuffer, center, radius, |framebuffer, x, y, offset| {
+        let distance = offset.magnitude() / radius;
+        if distance >= 1.0 {
+            return;
+        }
+        let angle = offset.y.atan2(offset.x);
+        let rays = (angle * STARBURST_RAYS).cos().abs().powf(60.0)
+            + (angle * STARBURST_RAYS + PI / 2.0).cos().abs().powf(120.0) * 0.5;
+        let falloff = (1.0 - distance).powi(3);
+        let halo = (-distance * distance * 30.0).exp() * 0.5;
+        let light = color * ((rays * falloff + halo) * 0.9);
+        framebuffer.add_point(x, y, f32::NEG_INFINITY, Color::from_float(light.x, light.y, light.z));
+    });
+}
+
+// Reflejo interno de la lente: disco tenue con el borde algo más brillante, o anillo
+fn draw_ghost(framebuffer: &mut Framebuffer, center: Vec2, radius: f32, color: Vec3, ring: bool) {
+    for_each_pixel(framebuffer, center, radius, |framebuffer, x, y, offset| {
+        let distance = offset.magnitude() / radius;
+        if distance >= 1.0 {
+            return;
+        }
+        let shape = if ring {
+            (-((distance - 0.85) / 0.06).powi(2)).exp()
+        } else {
+            0.6 + 0.4 * distance.powi(4)
+        };
+        // Borde suave para que los fantasmas no se vean recortados
+        let edge = ((1.0 - distance) / 0.1).min(1.0);
+        let light = color * (shape * edge);
+        framebuffer.add_point(x, y, f32::NEG_INFINITY, Color::from_float(light.x, light.y, light.z));
+    });
+}
+
+// Recorre los píxeles visibles del cuadrado que envuelve un círculo de la pantalla
+fn for_each_pixel<F: FnMut(&mut Framebuffer, usize, usize, Vec2)>(framebuffer: &mut Framebuffer, center: Vec2, radius: f32, mut draw: F) {
+    let min_x = (center.x - radius).floor().max(0.0);
+    let min_y = (center.y - radius).floor().max(0.0);
+    let max_x = (center.x + radius).ceil().min(framebuffer.width as f32 - 1.0);
+    let max_y = (center.y + radius).ceil().min(framebuffer.height as f32 - 1.0);
+    if max_x < min_x || max_y < min_y {
+        return;
+    }
+
+    for y in min_y as usize..=max_y as usize {
+        for x in min_x as usize..=max_x as usize {
+            let offset = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) - center;
+            draw(framebuffer, x, y, offset);
+        }
+    }
+}
@@ -21,9 +21,15 @@ pub fn line(a: &Vertex, b: &Vertex) -> Vec<Fragment> {
 
     let mut err = if dx > dy { dx / 2 } else { -dy / 2 };
 
+    // La profundidad se interpola por pasos a lo largo del eje dominante, así también
+    // funciona con líneas verticales
+    let steps = dx.max(dy).max(1) as f32;
+    let mut step = 0;
+
     loop {
-        let z = start.z + (end.z - start.z) * (x0 - start.x as i32) as f32 / (end.x - start.x) as f32;
+        let z = start.z + (end.z - start.z) * (step as f32 / steps);
         fragments.push(Fragment::new(x0 as f32, y0 as f32, Color::new(255, 255, 255), z));
+        step += 1;
 
         if x0 == x1 && y0 == y1 { break; }
 
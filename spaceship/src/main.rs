@@ -1,13 +1,16 @@
-use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4, look_at, perspective};
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat3, Mat4, look_at, perspective};
 use minifb::{Key, Window, WindowOptions};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::f32::consts::PI;
+use std::sync::Arc;
 
 mod framebuffer;
 mod triangle;
 mod line;
 mod vertex;
 mod obj;
+mod mesh;
+mod gltf_loader;
 mod color;
 mod fragment;
 mod shaders;
@@ -15,30 +18,126 @@ mod sphere;
 mod fragment_shader;
 mod ring;
 mod skybox;
+mod texture;
+mod atmosphere;
+mod noise;
+mod clouds;
+mod star;
+mod billboard;
+mod planet;
+mod particles;
+mod debug_view;
+mod lens_flare;
+mod god_rays;
+mod export;
+mod bake;
+mod assets;
+mod scene;
+mod cli;
 
 use framebuffer::Framebuffer;
 use vertex::Vertex;
 use obj::Obj;
-use triangle::triangle;
-use shaders::vertex_shader;
+use triangle::{triangle, wireframe};
+use shaders::{vertex_shader, normal_matrix, clip_near};
 use sphere::create_sphere;
-use ring::create_ring;
-use fragment_shader::ShaderType;
+use ring::{create_ring, RingSystem, RingShadow};
+use fragment_shader::{ShaderType, BlendMode, DepthCue};
 use line::line;
 use color::Color;
 use skybox::Skybox;
+use texture::Texture;
+use atmosphere::Atmosphere;
+use clouds::{CloudLayer, CloudShadow};
+use star::Star;
+use billboard::{create_billboard, billboard_matrix};
+use planet::PlanetParams;
+use particles::{ParticleSystem, ParticleConfig, Emitter};
+use debug_view::{DebugView, RenderMode, triangle_id_color, show_depth};
+use lens_flare::LensFlare;
+use god_rays::GodRays;
+use export::SceneSnapshot;
+use bake::{BakedSurface, BakeSettings, Projection};
+use assets::AssetManager;
+use scene::{Scene, SceneError};
+use cli::Command;
 
 
+#[derive(Clone)]
 pub struct Uniforms {
     pub model_matrix: Mat4,
+    pub normal_matrix: Mat3,
     pub view_matrix: Mat4,
     pub projection_matrix: Mat4,
     pub viewport_matrix: Mat4,
     pub time: u32,
+    // Posición del sol en el mundo; todas las superficies se iluminan desde aquí
+    pub light_position: Vec3,
+    // Color e intensidad de la luz del sol que llega al objeto dibujado
+    pub light_color: Vec3,
+    pub camera_position: Vec3,
+    pub cloud_shadow: Option<CloudShadow>,
+    pub ring_shadow: Option<RingShadow>,
+    pub diffuse_texture: Option<Arc<Texture>>,
+    pub normal_texture: Option<Arc<Texture>>,
+    // Material PBR de la malla: 0 = dieléctrico, 1 = metal; rugosidad 1 = mate
+    pub metallic: f32,
+    pub roughness: f32,
+    // Superficie horneada del cuerpo; los shaders la muestrean en vez de calcularla
+    pub baked_surface: Option<Arc<BakedSurface>>,
+    pub debug_view: DebugView,
+    pub depth_cue: Option<DepthCue>,
+    pub render_mode: RenderMode,
+    // Color de las aristas en los modos de alambre
+    pub wireframe_color: Color,
+}
+
+impl Uniforms {
+    // Uniforms comunes a todo el cuadro; cada objeto aporta luego su matriz de modelo
+    fn new(
+        view_matrix: Mat4,
+        projection_matrix: Mat4,
+        viewport_matrix: Mat4,
+        time: u32,
+        light_position: Vec3,
+        camera_position: Vec3,
+    ) -> Self {
+        Uniforms {
+            model_matrix: Mat4::identity(),
+            normal_matrix: Mat3::identity(),
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time,
+            light_position,
+            light_color: Vec3::new(1.0, 1.0, 1.0),
+            camera_position,
+            cloud_shadow: None,
+            ring_shadow: None,
+            diffuse_texture: None,
+            normal_texture: None,
+            metallic: 0.0,
+            roughness: 1.0,
+            baked_surface: None,
+            debug_view: DebugView::Final,
+            depth_cue: None,
+            render_mode: RenderMode::Shaded,
+            wireframe_color: Color::new(80, 255, 120),
+        }
+    }
+
+    fn with_model(&self, model_matrix: Mat4) -> Self {
+        Uniforms {
+            model_matrix,
+            normal_matrix: normal_matrix(&model_matrix),
+            ..self.clone()
+        }
+    }
 }
 
 // Estructura para representar un cuerpo celeste
 struct CelestialBody {
+    name: String,
     position: Vec3,
     scale: f32,
     orbit_radius: f32,
@@ -47,15 +146,26 @@ struct CelestialBody {
     shader_type: ShaderType,
     current_orbit_angle: f32,
     current_rotation_angle: f32,
-    has_rings: bool,
-    moon: Option<Box<CelestialBody>>,
+    // Color y segmentos de la línea de la órbita, si se dibuja
+    orbit_line: Option<(Color, u32)>,
+    rings: Option<RingSystem>,
+    // Inclinación del plano de los anillos respecto al plano de la órbita (radianes)
+    ring_inclination: f32,
+    // Sólo las estrellas emiten luz
+    star: Option<Star>,
+    atmosphere: Option<Atmosphere>,
+    clouds: Option<CloudLayer>,
+    current_cloud_angle: f32,
+    moons: Vec<CelestialBody>,
+    // Parte estática del shader horneada en texturas, si el shader lo permite
+    baked_surface: Option<Arc<BakedSurface>>,
 }
 
 struct Spaceship {
     position: Vec3,
     rotation: Vec3,
-    velocity: Vec3,
-    scale: f32,
+    // Radio de la esfera envolvente del modelo (ya importado con su tamaño final)
+    collision_radius: f32,
     speed: f32,
     rotation_speed: f32,
     collision_cooldown: f32,
@@ -63,6 +173,7 @@ struct Spaceship {
 
 impl CelestialBody {
     fn new(
+        name: &str,
         orbit_radius: f32,
         scale: f32,
         orbit_speed: f32,
@@ -70,6 +181,7 @@ impl CelestialBody {
         shader_type: ShaderType,
     ) -> Self {
         Self {
+            name: name.to_string(),
             position: Vec3::new(orbit_radius, 0.0, 0.0),
             scale,
             orbit_radius,
@@ -78,18 +190,82 @@ impl CelestialBody {
             shader_type,
             current_orbit_angle: 0.0,
             current_rotation_angle: 0.0,
-            has_rings: false,
-            moon: None,
+            orbit_line: None,
+            rings: None,
+            ring_inclination: 0.0,
+            star: None,
+            atmosphere: None,
+            clouds: None,
+            current_cloud_angle: 0.0,
+            moons: Vec::new(),
+            baked_surface: None,
+        }
+    }
+
+    // Estrella fija en el origen del sistema
+    fn star(name: &str, scale: f32, rotation_speed: f32, star: Star) -> Self {
+        Self {
+            star: Some(star),
+            ..Self::new(name, 0.0, scale, 0.0, rotation_speed, ShaderType::Sun(star))
+        }
+    }
+
+    // Planeta procedural: la atmósfera y las nubes también salen de sus parámetros
+    fn procedural(name: &str, orbit_radius: f32, scale: f32, orbit_speed: f32, rotation_speed: f32, params: PlanetParams) -> Self {
+        let mut body = Self::new(name, orbit_radius, scale, orbit_speed, rotation_speed, ShaderType::Procedural(params));
+        body.set_params(params);
+        body
+    }
+
+    fn set_params(&mut self, params: PlanetParams) {
+        self.shader_type = ShaderType::Procedural(params);
+        self.atmosphere = params.atmosphere();
+        self.clouds = params.clouds();
+        // Otra superficie: se vuelve a hornear con la misma configuración
+        let settings = self.baked_surface.as_ref().map(|baked| baked.settings);
+        self.bake_surface(settings);
+    }
+
+    // Hornea la superficie del cuerpo y de sus lunas; `None` vuelve a los shaders en vivo
+    fn bake_surface(&mut self, settings: Option<BakeSettings>) {
+        self.baked_surface = settings
+            .and_then(|settings| BakedSurface::bake(&self.shader_type, settings))
+            .map(Arc::new);
+        for moon in &mut self.moons {
+            moon.bake_surface(settings);
         }
     }
 
-    fn with_rings(mut self) -> Self {
-        self.has_rings = true;
+    // Ángulo inicial sobre la órbita (radianes)
+    fn with_orbit_phase(mut self, phase: f32) -> Self {
+        self.current_orbit_angle = phase.rem_euclid(2.0 * PI);
+        self.position = Vec3::new(self.orbit_radius * phase.cos(), 0.0, self.orbit_radius * phase.sin());
+        self
+    }
+
+    fn with_orbit_line(mut self, color: Color, segments: u32) -> Self {
+        self.orbit_line = Some((color, segments));
+        self
+    }
+
+    fn with_rings(mut self, rings: RingSystem, inclination: f32) -> Self {
+        self.rings = Some(rings);
+        self.ring_inclination = inclination;
+        self
+    }
+
+    fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    fn with_clouds(mut self, clouds: CloudLayer) -> Self {
+        self.clouds = Some(clouds);
         self
     }
 
     fn with_moon(mut self, moon: CelestialBody) -> Self {
-        self.moon = Some(Box::new(moon));
+        self.moons.push(moon);
         self
     }
 
@@ -107,13 +283,36 @@ impl CelestialBody {
             self.current_rotation_angle -= 2.0 * PI;
         }
 
-        if let Some(moon) = &mut self.moon {
+        if let Some(clouds) = &self.clouds {
+            self.current_cloud_angle = (self.current_cloud_angle + clouds.rotation_speed * delta_time).rem_euclid(2.0 * PI);
+        }
+
+        for moon in &mut self.moons {
             moon.update(delta_time);
             moon.position.x = self.position.x + moon.orbit_radius * moon.current_orbit_angle.cos();
             moon.position.z = self.position.z + moon.orbit_radius * moon.current_orbit_angle.sin();
         }
     }
     
+    fn cloud_shadow(&self) -> Option<CloudShadow> {
+        self.clouds.map(|layer| CloudShadow {
+            layer,
+            rotation: self.current_cloud_angle,
+        })
+    }
+
+    fn ring_shadow(&self) -> Option<RingShadow> {
+        self.rings.as_ref().map(|rings| RingShadow {
+            rings: rings.clone(),
+            normal: self.ring_normal(),
+        })
+    }
+
+    // Normal del plano de los anillos: el eje Y inclinado sobre X, igual que su matriz de modelo
+    fn ring_normal(&self) -> Vec3 {
+        Vec3::new(0.0, self.ring_inclination.cos(), self.ring_inclination.sin())
+    }
+
     fn get_collision_radius(&self) -> f32 {
         self.scale
     }
@@ -125,43 +324,45 @@ struct SolarSystem {
 }
 
 impl SolarSystem {
+    fn bake_surfaces(&mut self, settings: Option<BakeSettings>) {
+        let start = std::time::Instant::now();
+        self.sun.bake_surface(settings);
+        for planet in &mut self.planets {
+            planet.bake_surface(settings);
+        }
+        match settings {
+            Some(settings) => println!(
+                "Superficies horneadas ({:?}, resolución {}) en {:.2} s",
+                settings.projection, settings.resolution, start.elapsed().as_secs_f32()
+            ),
+            None => println!("Superficies calculadas en vivo"),
+        }
+    }
+
+    // Luz del sol que llega a un punto del sistema
+    fn light_at(&self, position: Vec3) -> Vec3 {
+        match &self.sun.star {
+            Some(star) => star.light_at((position - self.sun.position).magnitude()),
+            None => Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+
     // Detecta colisiones y empuja la nave fuera de los cuerpos celestes
     fn check_and_resolve_collision(&self, spaceship_pos: Vec3, spaceship_radius: f32) -> Option<(String, Vec3)> {
-        let direction_from_sun = spaceship_pos - self.sun.position;
-        let distance_to_sun = direction_from_sun.magnitude();
-        let min_distance_sun = self.sun.get_collision_radius() + spaceship_radius;
-        
-        if distance_to_sun < min_distance_sun {
-            let push_direction = direction_from_sun.normalize();
-            let new_position = self.sun.position + push_direction * min_distance_sun;
-            return Some(("Sol".to_string(), new_position));
-        }
-        
-        for (i, planet) in self.planets.iter().enumerate() {
-            let direction_from_planet = spaceship_pos - planet.position;
-            let distance = direction_from_planet.magnitude();
-            let min_distance = planet.get_collision_radius() + spaceship_radius;
-            
-            if distance < min_distance {
-                let push_direction = direction_from_planet.normalize();
-                let new_position = planet.position + push_direction * min_distance;
-                let planet_names = ["Planeta Tierra", "Planeta Gaseoso", "Planeta Helado"];
-                return Some((planet_names[i].to_string(), new_position));
-            }
-            
-            if let Some(moon) = &planet.moon {
-                let direction_from_moon = spaceship_pos - moon.position;
-                let distance_to_moon = direction_from_moon.magnitude();
-                let min_distance_moon = moon.get_collision_radius() + spaceship_radius;
-                
-                if distance_to_moon < min_distance_moon {
-                    let push_direction = direction_from_moon.normalize();
-                    let new_position = moon.position + push_direction * min_distance_moon;
-                    return Some(("Luna".to_string(), new_position));
-                }
+        let moons = self.planets.iter().flat_map(|planet| &planet.moons);
+        let bodies = std::iter::once(&self.sun).chain(&self.planets).chain(moons);
+
+        for body in bodies {
+            let direction = spaceship_pos - body.position;
+            let min_distance = body.get_collision_radius() + spaceship_radius;
+
+            if direction.magnitude() < min_distance {
+                let push_direction = direction.normalize();
+                let new_position = body.position + push_direction * min_distance;
+                return Some((body.name.clone(), new_position));
             }
         }
-        
+
         None
     }
 }
@@ -172,11 +373,9 @@ fn draw_orbit(
     radius: f32,
     segments: u32,
     color: Color,
-    view_matrix: &Mat4,
-    projection_matrix: &Mat4,
-    viewport_matrix: &Mat4,
+    uniforms: &Uniforms,
 ) {
-    let mvp = projection_matrix * view_matrix;
+    let mvp = uniforms.projection_matrix * uniforms.view_matrix;
     
     for i in 0..segments {
         let angle1 = (i as f32 / segments as f32) * 2.0 * PI;
@@ -217,7 +416,7 @@ fn draw_orbit(
             continue;
         }
         
-        let viewport = viewport_matrix;
+        let viewport = uniforms.viewport_matrix;
         let p1_screen = viewport * Vec4::new(p1_ndc.x, p1_ndc.y, p1_ndc.z, 1.0);
         let p2_screen = viewport * Vec4::new(p2_ndc.x, p2_ndc.y, p2_ndc.z, 1.0);
         
@@ -228,19 +427,19 @@ fn draw_orbit(
         
         let v1 = Vertex {
             position: p1,
-            normal: Vec3::new(0.0, 1.0, 0.0),
             tex_coords: Vec2::new(0.0, 0.0),
-            color: color.clone(),
+            color,
             transformed_position: Vec3::new(p1_screen.x, p1_screen.y, p1_screen.z),
-            transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+            world_position: p1,
+            ..Vertex::default()
         };
         let v2 = Vertex {
             position: p2,
-            normal: Vec3::new(0.0, 1.0, 0.0),
             tex_coords: Vec2::new(0.0, 0.0),
-            color: color.clone(),
+            color,
             transformed_position: Vec3::new(p2_screen.x, p2_screen.y, p2_screen.z),
-            transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+            world_position: p2,
+            ..Vertex::default()
         };
         
         let fragments = line(&v1, &v2);
@@ -327,93 +526,248 @@ fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Ve
         transformed_vertices.push(transformed);
     }
 
+    let mvp = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix;
     let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    for (tri, source) in transformed_vertices.chunks_exact(3).zip(vertex_array.chunks_exact(3)) {
+        let clip = [0, 1, 2].map(|i| mvp * Vec4::new(source[i].position.x, source[i].position.y, source[i].position.z, 1.0));
+        // Los triángulos detrás del plano lejano no se dibujan; los que cruzan el cercano
+        // se recortan para que no se pierdan al acercarse la cámara
+        if clip.iter().all(|position| position.z > position.w) {
+            continue;
+        }
+        triangles.extend(clip_near([&tri[0], &tri[1], &tri[2]], clip, uniforms));
+    }
+
+    // Los modos de depuración muestran sólo la geometría opaca
+    let blend_mode = shader_type.blend_mode();
+    if uniforms.debug_view != DebugView::Final && blend_mode != BlendMode::Opaque {
+        return;
+    }
+
+    // La atmósfera, las nubes y la corona repiten la esfera del planeta o son un simple
+    // cuadrado: en modo alambre no se dibujan y en el mixto no llevan aristas
+    let layer = matches!(shader_type, ShaderType::Atmosphere(_) | ShaderType::Clouds(_) | ShaderType::Corona(_));
+    match uniforms.render_mode {
+        RenderMode::Shaded => shade_triangles(framebuffer, uniforms, &triangles, shader_type, blend_mode),
+        RenderMode::Wireframe => {
+            if !layer {
+                draw_wireframe(framebuffer, uniforms, &triangles);
+            }
+        }
+        RenderMode::ShadedWireframe => {
+            shade_triangles(framebuffer, uniforms, &triangles, shader_type, blend_mode);
+            if !layer {
+                draw_wireframe(framebuffer, uniforms, &triangles);
+            }
         }
     }
+}
+
+// Dibuja la malla y, si en este cuadro se captura la escena para exportarla, la agrega con
+// la transformación y el shader con que se dibujó
+fn draw(
+    framebuffer: &mut Framebuffer,
+    snapshot: &mut Option<SceneSnapshot>,
+    name: &str,
+    uniforms: &Uniforms,
+    vertex_array: &[Vertex],
+    shader_type: &ShaderType,
+) {
+    render(framebuffer, uniforms, vertex_array, shader_type);
+    if let Some(snapshot) = snapshot {
+        snapshot.add(name, vertex_array, uniforms, shader_type);
+    }
+}
 
+fn shade_triangles(framebuffer: &mut Framebuffer, uniforms: &Uniforms, triangles: &[[Vertex; 3]], shader_type: &ShaderType, blend_mode: BlendMode) {
     let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2], uniforms, shader_type));
+    for (id, tri) in triangles.iter().enumerate() {
+        let mut triangle_fragments = triangle(&tri[0], &tri[1], &tri[2], uniforms, shader_type);
+        if uniforms.debug_view == DebugView::TriangleId {
+            for fragment in &mut triangle_fragments {
+                fragment.color = triangle_id_color(id);
+            }
+        }
+        fragments.extend(triangle_fragments);
     }
 
     for fragment in fragments {
+        // Los fragmentos a la izquierda o arriba de la pantalla se saturarían a 0 al convertirlos
+        if fragment.position.x < 0.0 || fragment.position.y < 0.0 {
+            continue;
+        }
         let x = fragment.position.x as usize;
         let y = fragment.position.y as usize;
         if x < framebuffer.width && y < framebuffer.height {
-            let color = fragment.color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+            match blend_mode {
+                BlendMode::Opaque => {
+                    framebuffer.set_current_color(fragment.color.to_hex());
+                    framebuffer.point(x, y, fragment.depth);
+                }
+                BlendMode::Alpha => {
+                    framebuffer.blend_point(x, y, fragment.depth, fragment.color, fragment.alpha);
+                }
+                BlendMode::Additive => {
+                    framebuffer.add_point(x, y, fragment.depth, fragment.color * fragment.alpha);
+                }
+            }
         }
     }
 }
 
+// Margen de profundidad de las aristas, relativo a la distancia a la cámara: la profundidad
+// NDC no es lineal y un margen fijo sobraría cerca y faltaría lejos
+const WIREFRAME_BIAS: f32 = 0.002;
+
+// Aristas de los triángulos, probadas contra el z-buffer de lo ya dibujado
+fn draw_wireframe(framebuffer: &mut Framebuffer, uniforms: &Uniforms, triangles: &[[Vertex; 3]]) {
+    // Un vértice casi en el plano de la cámara se proyecta a millones de píxeles
+    let limit = (framebuffer.width.max(framebuffer.height) * 4) as f32;
+    for tri in triangles {
+        if tri.iter().any(|v| v.transformed_position.x.abs() > limit || v.transformed_position.y.abs() > limit) {
+            continue;
+        }
+        for fragment in wireframe(&tri[0], &tri[1], &tri[2]) {
+            if fragment.position.x < 0.0 || fragment.position.y < 0.0 {
+                continue;
+            }
+            // 1 - z es aproximadamente proporcional a 1 / distancia
+            let bias = WIREFRAME_BIAS * (1.0 - fragment.depth).max(0.0);
+            framebuffer.line_point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth, bias, uniforms.wireframe_color);
+        }
+    }
+}
+
+// Nombre de una luna en la escena exportada: la primera de cada planeta es luna_<planeta> y
+// las siguientes luna_<planeta>_<n>
+fn moon_export_name(planet: usize, moon: usize) -> String {
+    match moon {
+        0 => format!("luna_{}", planet + 1),
+        _ => format!("luna_{}_{}", planet + 1, moon + 1),
+    }
+}
+
+// Teclas de la ventana; sin ventana (--headless) no hay ninguna presionada
+fn key_pressed(window: &Option<Window>, key: Key) -> bool {
+    window.as_ref().is_some_and(|window| window.is_key_pressed(key, minifb::KeyRepeat::No))
+}
+
+fn key_down(window: &Option<Window>, key: Key) -> bool {
+    window.as_ref().is_some_and(|window| window.is_key_down(key))
+}
+
+// Dirección del chorro del motor en las coordenadas del modelo (la cola de la nave apunta a -Z)
+const ENGINE_DIRECTION: Vec3 = Vec3::new(0.0, 0.0, -1.0);
+
 fn main() {
-    let window_width = 800;
-    let window_height = 800;
-    let framebuffer_width = 800;
-    let framebuffer_height = 800;
-    let frame_delay = Duration::from_millis(16);
+    let options = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            eprintln!("Use --help para ver las opciones");
+            std::process::exit(2);
+        }
+    };
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Sistema Solar - Software Renderer",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
+    let window_width = options.width;
+    let window_height = options.height;
+    // Con --scale el render tiene otra resolución y la ventana lo estira
+    let (framebuffer_width, framebuffer_height) = options.framebuffer_size();
+    let frame_time = options.fps_cap.map(|fps| Duration::from_secs_f32(1.0 / fps as f32));
+    bake::set_threads(options.threads);
 
-    window.set_position(500, 500);
-    window.update();
+    // El sistema solar y la nave salen del archivo de escena. Si el de por defecto no está en
+    // la carpeta de recursos se usa la copia incluida en el ejecutable; una escena con
+    // errores (o una indicada con --scene que no existe) no se abre.
+    let mut assets = AssetManager::new(options.assets.clone().unwrap_or_else(assets::locate_root));
+    println!("Recursos en {}", assets.root().display());
+    let scene = match &options.scene {
+        Some(path) => Scene::load(path),
+        None => match Scene::load(&assets.resolve(scene::DEFAULT_SCENE)) {
+            Err(SceneError::NotFound(path)) => {
+                println!("No existe {}; se usa la escena incluida", path.display());
+                Scene::parse(scene::BUILTIN_SCENE, "incluida")
+            }
+            result => result,
+        },
+    };
+    let Scene { system: mut solar_system, ship: mut ship_spawn } = scene.unwrap_or_else(|err| {
+        eprintln!("Error cargando la escena: {}", err);
+        std::process::exit(1);
+    });
+    if let Some(ship) = &options.ship {
+        ship_spawn.model = ship.to_string_lossy().into_owned();
+    }
+
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    // Sin ventana (--headless) se dibuja igual, sólo para --screenshot
+    let mut window = (!options.headless).then(|| {
+        let mut window = Window::new(
+            "Sistema Solar - Software Renderer",
+            window_width,
+            window_height,
+            WindowOptions::default(),
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("No se pudo abrir la ventana: {}", err);
+            std::process::exit(1);
+        });
+        window.set_position(500, 500);
+        window.update();
+        window
+    });
 
     framebuffer.set_background_color(0x000000);
 
-    let skybox = Skybox::new(800);
+    // Semillas por defecto: las de siempre, así el cielo no cambia sin --seed
+    let skybox = Skybox::new(options.stars, options.seed.unwrap_or(12345));
     let sphere = create_sphere(1.0, 50, 50);
-    let ring = create_ring(1.3, 2.0, 100);
+    let billboard = create_billboard();
     
-    let spaceship_obj = Obj::load("assets/Jett.obj").expect("Error cargando modelo de nave");
-    let spaceship_vertices = spaceship_obj.get_vertex_array();
+    // La nave se importa con los ejes, el pivote y el largo de la escena (por defecto centrada
+    // en su caja envolvente), así cualquier modelo queda del mismo tamaño y mirando hacia +Z.
+    // El formato (OBJ o glTF/GLB) se elige por la extensión del archivo.
+    let ship_import = ship_spawn.import;
+    let ship_model = assets.load_model(&ship_spawn.model, &ship_import).unwrap_or_else(|err| {
+        eprintln!("Error cargando modelo de nave: {}", err);
+        eprintln!("Se usa la nave de reemplazo");
+        assets.insert_fallback(&ship_spawn.model, &ship_import, Obj::placeholder_ship(&ship_import))
+    });
+    let spaceship_obj = assets.model(ship_model);
+    println!("Nave: {}", spaceship_obj.report());
+    let mut spaceship_batches = spaceship_obj.get_draw_batches();
     
     let mut spaceship = Spaceship {
-        position: Vec3::new(0.0, 8.0, 35.0),
-        rotation: Vec3::new(0.0, PI, 0.0),
-        velocity: Vec3::new(0.0, 0.0, 0.0),
-        scale: 0.08,
+        position: ship_spawn.position,
+        rotation: Vec3::new(0.0, ship_spawn.yaw, 0.0),
+        collision_radius: spaceship_obj.report().bounding_sphere.radius,
         speed: 0.15,
         rotation_speed: 0.03,
         collision_cooldown: 0.0,
     };
 
-    // Crear sistema solar
-    let mut solar_system = SolarSystem {
-        sun: CelestialBody {
-            position: Vec3::new(0.0, 0.0, 0.0),
-            scale: 2.0,
-            orbit_radius: 0.0,
-            orbit_speed: 0.0,
-            rotation_speed: 0.1,
-            shader_type: ShaderType::Sun,
-            current_orbit_angle: 0.0,
-            current_rotation_angle: 0.0,
-            has_rings: false,
-            moon: None,
-        },
-        planets: vec![
-            CelestialBody::new(8.0, 0.8, 0.5, 1.0, ShaderType::EarthLike)
-                .with_moon(CelestialBody::new(1.5, 0.3, 2.0, 0.5, ShaderType::Moon)),
-            CelestialBody::new(15.0, 1.5, 0.3, 0.8, ShaderType::GasGiant)
-                .with_rings(),
-            CelestialBody::new(22.0, 0.6, 0.2, 0.9, ShaderType::IcePlanet),
-        ],
-    };
+    let import = spaceship_obj.transform();
+    let mut engine_exhaust = Emitter::new(
+        ParticleConfig::engine_exhaust(),
+        import.point(ship_spawn.engine_nozzle),
+        import.direction(ENGINE_DIRECTION),
+    );
+    let mut particles = ParticleSystem::new(options.seed.unwrap_or(1));
+    let mut lens_flare = LensFlare::new();
+    let mut god_rays = GodRays::new(framebuffer_width, framebuffer_height);
+
+    // Disco de anillos de cada planeta, del tamaño de su perfil radial
+    let ring_meshes: Vec<Option<Vec<Vertex>>> = solar_system.planets.iter()
+        .map(|planet| planet.rings.as_ref().map(|rings| create_ring(rings.inner_radius(), rings.outer_radius(), 100)))
+        .collect();
+
+    // Las superficies estáticas se hornean una vez; las capas animadas siguen en vivo
+    let mut surface_bake = Some(BakeSettings::equirectangular(512));
+    solar_system.bake_surfaces(surface_bake);
 
     let mut time = 0u32;
     let delta_time = 0.016;
@@ -430,62 +784,141 @@ fn main() {
     println!("Otros:");
     println!("  ESPACIO: Pausar/Reanudar órbitas planetarias");
     println!("  O: Mostrar/Ocultar órbitas");
+    println!("  R: Generar otra variante de los planetas procedurales");
+    println!("  B: Superficies horneadas en textura equirectangular, en cubemap o en vivo");
+    println!("  V: Cambiar vista de depuración");
+    println!("  F: Activar/Desactivar atenuación por distancia");
+    println!("  M: Cambiar modo de dibujo (sombreado, alambre, sombreado con alambre)");
+    println!("  G: Mostrar/Ocultar rayos de luz del sol");
+    println!("  X: Exportar la escena a OBJ/MTL y PLY con los colores de los shaders (Shift+X: sin hornear)");
     println!("  ESC: Salir");
     println!("==================================");
 
     let mut paused = false;
     let mut show_orbits = true;
+    let mut debug_view = DebugView::Final;
+    let mut depth_cueing = false;
+    let mut render_mode = RenderMode::Shaded;
+    let mut show_god_rays = true;
 
-    while window.is_open() {
-        if window.is_key_pressed(Key::Escape, minifb::KeyRepeat::No) {
+    let mut frames_drawn = 0u64;
+    while window.as_ref().is_none_or(Window::is_open) && options.frames.is_none_or(|frames| frames_drawn < frames) {
+        let frame_start = Instant::now();
+        if key_pressed(&window, Key::Escape) {
             break;
         }
 
-        if window.is_key_pressed(Key::Space, minifb::KeyRepeat::No) {
+        // Si se edita el modelo de la nave (OBJ, MTL o texturas) se recarga en vivo
+        if assets.poll_changes().contains(&ship_model) {
+            let spaceship_obj = assets.model(ship_model);
+            spaceship_batches = spaceship_obj.get_draw_batches();
+            spaceship.collision_radius = spaceship_obj.report().bounding_sphere.radius;
+            engine_exhaust.offset = spaceship_obj.transform().point(ship_spawn.engine_nozzle);
+            engine_exhaust.direction = spaceship_obj.transform().direction(ENGINE_DIRECTION);
+        }
+
+        if key_pressed(&window, Key::Space) {
             paused = !paused;
             println!("{}", if paused { "Sistema pausado" } else { "Sistema activo" });
         }
 
-        if window.is_key_pressed(Key::O, minifb::KeyRepeat::No) {
+        if key_pressed(&window, Key::O) {
             show_orbits = !show_orbits;
             println!("{}", if show_orbits { "Órbitas visibles" } else { "Órbitas ocultas" });
         }
 
+        if key_pressed(&window, Key::V) {
+            debug_view = debug_view.next();
+            println!("Vista: {}", debug_view.name());
+        }
+
+        if key_pressed(&window, Key::F) {
+            depth_cueing = !depth_cueing;
+            println!("{}", if depth_cueing { "Atenuación por distancia activada" } else { "Atenuación por distancia desactivada" });
+        }
+
+        if key_pressed(&window, Key::M) {
+            render_mode = render_mode.next();
+            println!("Modo de dibujo: {}", render_mode.name());
+        }
+
+        if key_pressed(&window, Key::G) {
+            show_god_rays = !show_god_rays;
+            println!("{}", if show_god_rays { "Rayos de luz visibles" } else { "Rayos de luz ocultos" });
+        }
+
+        // La escena de este cuadro se captura mientras se dibuja y se guarda al final
+        let mut snapshot = None;
+        if key_pressed(&window, Key::X) {
+            let shift = key_down(&window, Key::LeftShift) || key_down(&window, Key::RightShift);
+            snapshot = Some(SceneSnapshot::new(!shift));
+        }
+
+        if key_pressed(&window, Key::B) {
+            surface_bake = match surface_bake.map(|settings| settings.projection) {
+                Some(Projection::Equirectangular) => Some(BakeSettings::cubemap(256)),
+                Some(Projection::Cubemap) => None,
+                None => Some(BakeSettings::equirectangular(512)),
+            };
+            solar_system.bake_surfaces(surface_bake);
+        }
+
+        if key_pressed(&window, Key::R) {
+            for planet in &mut solar_system.planets {
+                if let ShaderType::Procedural(params) = planet.shader_type {
+                    let params = params.randomized();
+                    planet.set_params(params);
+                    println!("Planeta procedural con semilla {}", params.seed);
+                }
+            }
+        }
+
         let yaw = spaceship.rotation.y;
-        let pitch = spaceship.rotation.x;
+        let previous_position = spaceship.position;
         
-        if window.is_key_down(Key::W) {
+        if key_down(&window, Key::W) {
             spaceship.position.x += yaw.sin() * spaceship.speed;
             spaceship.position.z += yaw.cos() * spaceship.speed;
         }
-        if window.is_key_down(Key::S) {
+        if key_down(&window, Key::S) {
             spaceship.position.x -= yaw.sin() * spaceship.speed;
             spaceship.position.z -= yaw.cos() * spaceship.speed;
         }
         
-        if window.is_key_down(Key::A) {
+        if key_down(&window, Key::A) {
             spaceship.rotation.y += spaceship.rotation_speed;
         }
-        if window.is_key_down(Key::D) {
+        if key_down(&window, Key::D) {
             spaceship.rotation.y -= spaceship.rotation_speed;
         }
         
-        if window.is_key_down(Key::Up) {
+        if key_down(&window, Key::Up) {
             spaceship.rotation.x += spaceship.rotation_speed * 0.5;
             spaceship.rotation.x = spaceship.rotation.x.min(PI / 6.0);
         }
-        if window.is_key_down(Key::Down) {
+        if key_down(&window, Key::Down) {
             spaceship.rotation.x -= spaceship.rotation_speed * 0.5;
             spaceship.rotation.x = spaceship.rotation.x.max(-PI / 6.0);
         }
         
-        if window.is_key_down(Key::Q) {
+        if key_down(&window, Key::Q) {
             spaceship.position.y += spaceship.speed;
         }
-        if window.is_key_down(Key::E) {
+        if key_down(&window, Key::E) {
             spaceship.position.y -= spaceship.speed;
         }
 
+        // El chorro del motor sigue al acelerador: pleno con W, menor al frenar y un
+        // resplandor mínimo en reposo
+        let throttle = if key_down(&window, Key::W) {
+            1.0
+        } else if key_down(&window, Key::S) {
+            0.35
+        } else {
+            0.1
+        };
+        engine_exhaust.intensity += (throttle - engine_exhaust.intensity) * f32::min(delta_time * 8.0, 1.0);
+
         if !paused {
             solar_system.sun.update(delta_time);
             for planet in &mut solar_system.planets {
@@ -505,13 +938,14 @@ fn main() {
             spaceship.collision_cooldown -= delta_time;
         }
         
-        let spaceship_collision_radius = spaceship.scale * 5.0;
-        if let Some((collision_name, corrected_position)) = solar_system.check_and_resolve_collision(spaceship.position, spaceship_collision_radius) {
+        if let Some((collision_name, corrected_position)) = solar_system.check_and_resolve_collision(spaceship.position, spaceship.collision_radius) {
             spaceship.position = corrected_position;
             
             if spaceship.collision_cooldown <= 0.0 {
                 println!("¡COLISIÓN con {}!", collision_name);
                 spaceship.collision_cooldown = 1.0;
+                particles.burst(&ParticleConfig::explosion(), spaceship.position, Vec3::zeros(), Vec3::zeros(), 60);
+                particles.burst(&ParticleConfig::debris(), spaceship.position, Vec3::zeros(), Vec3::zeros(), 40);
             }
         }
 
@@ -519,43 +953,34 @@ fn main() {
 
         framebuffer.clear();
         
-        skybox.render(&mut framebuffer);
+        if debug_view == DebugView::Final {
+            skybox.render(&mut framebuffer);
+        }
 
         let view_matrix = create_view_matrix(camera_position, spaceship.position, Vec3::new(0.0, 1.0, 0.0));
+        let mut frame_uniforms = Uniforms::new(
+            view_matrix,
+            projection_matrix,
+            viewport_matrix,
+            time,
+            solar_system.sun.position,
+            camera_position,
+        );
+        frame_uniforms.debug_view = debug_view;
+        frame_uniforms.depth_cue = depth_cueing.then(DepthCue::subtle);
+        frame_uniforms.render_mode = render_mode;
 
-        if show_orbits {
-            draw_orbit(
-                &mut framebuffer,
-                Vec3::new(0.0, 0.0, 0.0),
-                8.0,
-                100,
-                Color::new(0, 255, 100),
-                &view_matrix,
-                &projection_matrix,
-                &viewport_matrix
-            );
-
-            draw_orbit(
-                &mut framebuffer,
-                Vec3::new(0.0, 0.0, 0.0),
-                15.0,
-                120,
-                Color::new(200, 100, 255),
-                &view_matrix,
-                &projection_matrix,
-                &viewport_matrix
-            );
-
-            draw_orbit(
-                &mut framebuffer,
-                Vec3::new(0.0, 0.0, 0.0),
-                22.0,
-                140,
-                Color::new(100, 200, 255),
-                &view_matrix,
-                &projection_matrix,
-                &viewport_matrix
-            );
+        if show_orbits && debug_view == DebugView::Final {
+            for planet in &solar_system.planets {
+                if let Some((color, segments)) = planet.orbit_line {
+                    draw_orbit(&mut framebuffer, solar_system.sun.position, planet.orbit_radius, segments, color, &frame_uniforms);
+                }
+                for moon in &planet.moons {
+                    if let Some((color, segments)) = moon.orbit_line {
+                        draw_orbit(&mut framebuffer, planet.position, moon.orbit_radius, segments, color, &frame_uniforms);
+                    }
+                }
+            }
         }
 
         {
@@ -564,87 +989,145 @@ fn main() {
                 solar_system.sun.scale,
                 Vec3::new(0.0, solar_system.sun.current_rotation_angle, 0.0)
             );
-            let sun_uniforms = Uniforms {
-                model_matrix: sun_model_matrix,
-                view_matrix,
-                projection_matrix,
-                viewport_matrix,
-                time,
-            };
-            render(&mut framebuffer, &sun_uniforms, &sphere, &solar_system.sun.shader_type);
+            let sun_uniforms = frame_uniforms.with_model(sun_model_matrix);
+            draw(&mut framebuffer, &mut snapshot, "sol", &sun_uniforms, &sphere, &solar_system.sun.shader_type);
         }
 
-        for planet in &solar_system.planets {
+        for (i, planet) in solar_system.planets.iter().enumerate() {
                 let planet_model_matrix = create_model_matrix(
                     planet.position,
                     planet.scale,
                     Vec3::new(0.0, planet.current_rotation_angle, 0.0)
                 );
-                let planet_uniforms = Uniforms {
-                    model_matrix: planet_model_matrix,
-                    view_matrix,
-                    projection_matrix,
-                    viewport_matrix,
-                    time,
-                };
-                render(&mut framebuffer, &planet_uniforms, &sphere, &planet.shader_type);
-
-                if planet.has_rings {
-                    let ring_rotation = Vec3::new(PI / 6.0, planet.current_rotation_angle, 0.0);
-                    let ring_model_matrix = create_model_matrix(planet.position, planet.scale, ring_rotation);
-                    let ring_uniforms = Uniforms {
-                        model_matrix: ring_model_matrix,
-                        view_matrix,
-                        projection_matrix,
-                        viewport_matrix,
-                        time,
-                    };
-                    render(&mut framebuffer, &ring_uniforms, &ring, &ShaderType::Rings);
-                }
+                let planet_light = solar_system.light_at(planet.position);
+                let mut planet_uniforms = frame_uniforms.with_model(planet_model_matrix);
+                planet_uniforms.light_color = planet_light;
+                planet_uniforms.cloud_shadow = planet.cloud_shadow();
+                planet_uniforms.ring_shadow = planet.ring_shadow();
+                planet_uniforms.baked_surface = planet.baked_surface.clone();
+                draw(&mut framebuffer, &mut snapshot, &format!("planeta_{}", i + 1), &planet_uniforms, &sphere, &planet.shader_type);
 
-            if let Some(moon) = &planet.moon {
+            for (j, moon) in planet.moons.iter().enumerate() {
                     let moon_model_matrix = create_model_matrix(
                         moon.position,
                         moon.scale,
                         Vec3::new(0.0, moon.current_rotation_angle, 0.0)
                     );
-                    let moon_uniforms = Uniforms {
-                        model_matrix: moon_model_matrix,
-                        view_matrix,
-                        projection_matrix,
-                        viewport_matrix,
-                        time,
-                    };
-                    render(&mut framebuffer, &moon_uniforms, &sphere, &moon.shader_type);
+                    let mut moon_uniforms = frame_uniforms.with_model(moon_model_matrix);
+                    moon_uniforms.light_color = solar_system.light_at(moon.position);
+                    moon_uniforms.baked_surface = moon.baked_surface.clone();
+                    draw(&mut framebuffer, &mut snapshot, &moon_export_name(i, j), &moon_uniforms, &sphere, &moon.shader_type);
                 }
         }
 
-        // Corrección de orientación del modelo 3D
-        let spaceship_corrected_rotation = Vec3::new(
-            spaceship.rotation.x + PI,
-            spaceship.rotation.y,
-            spaceship.rotation.z
-        );
+        // La escala ya se aplicó al importar el modelo (`target_size`)
         let spaceship_model_matrix = create_model_matrix(
             spaceship.position,
-            spaceship.scale,
-            spaceship_corrected_rotation
+            1.0,
+            spaceship.rotation
         );
-        let spaceship_uniforms = Uniforms {
-            model_matrix: spaceship_model_matrix,
-            view_matrix,
-            projection_matrix,
-            viewport_matrix,
-            time,
-        };
-        render(&mut framebuffer, &spaceship_uniforms, &spaceship_vertices, &ShaderType::Spaceship);
+        let spaceship_velocity = (spaceship.position - previous_position) / delta_time;
+        particles.update(delta_time);
+        particles.emit(&mut engine_exhaust, &spaceship_model_matrix, spaceship_velocity, delta_time);
+
+        let mut spaceship_uniforms = frame_uniforms.with_model(spaceship_model_matrix);
+        spaceship_uniforms.light_color = solar_system.light_at(spaceship.position);
+        spaceship_uniforms.wireframe_color = Color::new(80, 200, 255);
+        for (i, batch) in spaceship_batches.iter().enumerate() {
+            let mut batch_uniforms = spaceship_uniforms.clone();
+            batch_uniforms.diffuse_texture = batch.material.diffuse_texture.clone();
+            batch_uniforms.normal_texture = batch.material.normal_texture.clone();
+            batch_uniforms.metallic = batch.material.metallic;
+            batch_uniforms.roughness = batch.material.roughness;
+            draw(&mut framebuffer, &mut snapshot, &format!("nave_{}", i + 1), &batch_uniforms, &batch.vertices, &ShaderType::Spaceship);
+        }
+
+        // Capas transparentes al final, sobre toda la geometría opaca
+        if let Some(star) = &solar_system.sun.star {
+            let corona_model_matrix = billboard_matrix(
+                solar_system.sun.position,
+                solar_system.sun.scale * star.corona_scale(),
+                &view_matrix
+            );
+            let corona_uniforms = frame_uniforms.with_model(corona_model_matrix);
+            draw(&mut framebuffer, &mut snapshot, "corona", &corona_uniforms, &billboard, &ShaderType::Corona(*star));
+        }
+
+        for (i, (planet, ring_mesh)) in solar_system.planets.iter().zip(&ring_meshes).enumerate() {
+            // Los anillos no giran con el planeta: su perfil es simétrico y así su plano no se tambalea
+            if let (Some(rings), Some(ring_mesh)) = (&planet.rings, ring_mesh) {
+                let ring_rotation = Vec3::new(planet.ring_inclination, 0.0, 0.0);
+                let ring_model_matrix = create_model_matrix(planet.position, planet.scale, ring_rotation);
+                let mut ring_uniforms = frame_uniforms.with_model(ring_model_matrix);
+                ring_uniforms.light_color = solar_system.light_at(planet.position);
+                ring_uniforms.wireframe_color = Color::new(255, 210, 110);
+                draw(&mut framebuffer, &mut snapshot, &format!("anillos_{}", i + 1), &ring_uniforms, ring_mesh, &ShaderType::Rings(rings.clone()));
+            }
+
+            if let Some(clouds) = &planet.clouds {
+                let cloud_model_matrix = create_model_matrix(
+                    planet.position,
+                    planet.scale * clouds.shell_scale(),
+                    Vec3::new(0.0, planet.current_cloud_angle, 0.0)
+                );
+                let mut cloud_uniforms = frame_uniforms.with_model(cloud_model_matrix);
+                cloud_uniforms.light_color = solar_system.light_at(planet.position);
+                draw(&mut framebuffer, &mut snapshot, &format!("nubes_{}", i + 1), &cloud_uniforms, &sphere, &ShaderType::Clouds(*clouds));
+            }
+
+            if let Some(atmosphere) = &planet.atmosphere {
+                let shell_model_matrix = create_model_matrix(
+                    planet.position,
+                    planet.scale * atmosphere.shell_scale(),
+                    Vec3::new(0.0, 0.0, 0.0)
+                );
+                let mut shell_uniforms = frame_uniforms.with_model(shell_model_matrix);
+                shell_uniforms.light_color = solar_system.light_at(planet.position);
+                draw(&mut framebuffer, &mut snapshot, &format!("atmosfera_{}", i + 1), &shell_uniforms, &sphere, &ShaderType::Atmosphere(*atmosphere));
+            }
+        }
+
+        match debug_view {
+            DebugView::Final => {
+                particles.render(&mut framebuffer, &frame_uniforms);
+                if let Some(star) = &solar_system.sun.star {
+                    if show_god_rays {
+                        god_rays.render(&mut framebuffer, &frame_uniforms, solar_system.sun.position, solar_system.sun.scale, star.light_color());
+                    }
+                    lens_flare.render(&mut framebuffer, &frame_uniforms, solar_system.sun.position, solar_system.sun.scale, star.light_color());
+                }
+            }
+            DebugView::Depth => show_depth(&mut framebuffer, &projection_matrix),
+            _ => {}
+        }
+
+        if let Some(snapshot) = snapshot {
+            let base = std::path::Path::new("export/escena");
+            match snapshot.save(base) {
+                Ok(()) => println!("Escena exportada ({} mallas) a {}.obj y {}.ply", snapshot.meshes.len(), base.display(), base.display()),
+                Err(err) => eprintln!("Error exportando la escena: {}", err),
+            }
+            // Junto a la escena, las texturas horneadas de cada cuerpo
+            let dir = base.parent().unwrap_or(std::path::Path::new("."));
+            for (i, planet) in solar_system.planets.iter().enumerate() {
+                let moons = planet.moons.iter().enumerate().map(|(j, moon)| (moon_export_name(i, j), moon));
+                for (name, body) in std::iter::once((format!("planeta_{}", i + 1), planet)).chain(moons) {
+                    if let Some(baked) = &body.baked_surface {
+                        if let Err(err) = baked.save_png(dir, &name) {
+                            eprintln!("Error exportando la textura de {}: {}", name, err);
+                        }
+                    }
+                }
+            }
+        }
 
         // Efecto visual de colisión
         if spaceship.collision_cooldown > 0.0 {
             let flash_intensity = (spaceship.collision_cooldown * 127.5) as u8;
-            let red_color = ((flash_intensity as u32) << 16) | 0x000000;
+            let red_color = (flash_intensity as u32) << 16;
             
-            let border_thickness = 10;
+            // En framebuffers muy chicos (--scale) el borde no puede pasar del tamaño de la imagen
+            let border_thickness = 10.min(framebuffer_width).min(framebuffer_height);
             for y in 0..framebuffer_height {
                 for x in 0..border_thickness {
                     framebuffer.set_current_color(red_color);
@@ -661,10 +1144,25 @@ fn main() {
             }
         }
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+        frames_drawn += 1;
+        if let Some(window) = &mut window {
+            window
+                .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+                .unwrap();
 
-        std::thread::sleep(frame_delay);
+            if let Some(frame_time) = frame_time {
+                std::thread::sleep(frame_time.saturating_sub(frame_start.elapsed()));
+            }
+        }
+    }
+
+    if let Some(path) = &options.screenshot {
+        match framebuffer.save_png(path) {
+            Ok(()) => println!("Captura guardada en {} ({} cuadros)", path.display(), frames_drawn),
+            Err(err) => {
+                eprintln!("Error guardando la captura {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
     }
 }
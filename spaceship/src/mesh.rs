@@ -0,0 +1,483 @@
+use std::collections::HashMap;
+use nalgebra_glm::{Mat3, Mat4, Vec2, Vec3, Vec4};
+
+// De dónde salen las normales de una malla importada
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum NormalMode {
+    // Las del archivo; si faltan se generan suaves
+    FromFile,
+    // Una normal por cara: aristas duras en todo el modelo
+    Flat,
+    // Promedio de las caras vecinas ponderado por el ángulo de cada esquina, salvo entre
+    // caras que forman un ángulo mayor que el de pliegue; reemplaza las del archivo
+    Smooth,
+}
+
+// Eje de coordenadas con sentido, para describir la orientación de un archivo
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Axis {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl Axis {
+    pub fn vector(self) -> Vec3 {
+        match self {
+            Axis::PosX => Vec3::new(1.0, 0.0, 0.0),
+            Axis::NegX => Vec3::new(-1.0, 0.0, 0.0),
+            Axis::PosY => Vec3::new(0.0, 1.0, 0.0),
+            Axis::NegY => Vec3::new(0.0, -1.0, 0.0),
+            Axis::PosZ => Vec3::new(0.0, 0.0, 1.0),
+            Axis::NegZ => Vec3::new(0.0, 0.0, -1.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Handedness {
+    Right,
+    // Sistemas de mano izquierda: el modelo se refleja y se invierte el orden de los triángulos
+    Left,
+}
+
+// Punto del modelo que queda en el origen después de importarlo
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Pivot {
+    // El origen del archivo
+    FileOrigin,
+    // El centro de la caja envolvente
+    BoundsCenter,
+}
+
+// Cómo se procesa la geometría de un modelo al importarlo. El motor usa +Y arriba y +Z
+// adelante (hacia donde avanza la nave); los ejes del archivo se convierten a esos.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ImportOptions {
+    pub normals: NormalMode,
+    // Ángulo máximo (radianes) entre dos caras para suavizar la arista que comparten
+    pub crease_angle: f32,
+    // Distancia por debajo de la cual dos vértices con los mismos atributos se unen; 0 = no unir
+    pub weld_tolerance: f32,
+    pub up_axis: Axis,
+    pub forward_axis: Axis,
+    pub handedness: Handedness,
+    pub pivot: Pivot,
+    // Si se indica, el modelo se escala para que su lado más largo mida esto
+    pub target_size: Option<f32>,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            normals: NormalMode::FromFile,
+            crease_angle: 60f32.to_radians(),
+            weld_tolerance: 1e-5,
+            up_axis: Axis::PosY,
+            forward_axis: Axis::PosZ,
+            handedness: Handedness::Right,
+            pivot: Pivot::FileOrigin,
+            target_size: None,
+        }
+    }
+}
+
+impl ImportOptions {
+    // Rotación (y reflejo, en mano izquierda) de los ejes del archivo a los del motor.
+    // `None` si arriba y adelante no son ejes perpendiculares.
+    pub fn axis_matrix(&self) -> Option<Mat3> {
+        let up = self.up_axis.vector();
+        let forward = self.forward_axis.vector();
+        if up.dot(&forward).abs() > 0.5 {
+            return None;
+        }
+        let side = up.cross(&forward);
+        let mirror = if self.handedness == Handedness::Left { -1.0 } else { 1.0 };
+        // Las columnas de `file` son los ejes del archivo; como es ortonormal, su inversa es la transpuesta
+        let file = Mat3::from_columns(&[side, up, forward]);
+        let engine = Mat3::from_columns(&[Vec3::new(mirror, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)]);
+        Some(engine * file.transpose())
+    }
+}
+
+// Transformación que se aplicó al importar: sirve para llevar puntos conocidos del archivo
+// (una tobera, un cañón) al espacio del modelo ya importado
+#[derive(Clone, Copy, Debug)]
+pub struct ImportTransform {
+    axes: Mat3,
+    pivot: Vec3,
+    scale: f32,
+}
+
+impl ImportTransform {
+    pub fn point(&self, point: Vec3) -> Vec3 {
+        (self.axes * point - self.pivot) * self.scale
+    }
+
+    pub fn direction(&self, direction: Vec3) -> Vec3 {
+        (self.axes * direction).normalize()
+    }
+}
+
+// Centra y escala todas las mallas de un modelo según las opciones. Las mallas ya deben
+// estar en los ejes del motor (ver `MeshData::transform`).
+pub fn normalize_meshes(meshes: &mut [MeshData], axes: Mat3, options: &ImportOptions) -> ImportTransform {
+    let bounds = Bounds::from_points(meshes.iter().flat_map(|mesh| &mesh.positions));
+    let pivot = match options.pivot {
+        Pivot::FileOrigin => Vec3::zeros(),
+        Pivot::BoundsCenter => bounds.center(),
+    };
+    let longest = bounds.size().max();
+    let scale = match options.target_size {
+        Some(size) if longest > 0.0 => size / longest,
+        _ => 1.0,
+    };
+
+    for mesh in meshes.iter_mut() {
+        for position in &mut mesh.positions {
+            *position = (*position - pivot) * scale;
+        }
+    }
+    ImportTransform { axes, pivot, scale }
+}
+
+// Qué cambió al procesar una malla
+#[derive(Clone, Copy, Default)]
+pub struct ProcessStats {
+    pub welded_vertices: usize,
+    pub degenerate_triangles: usize,
+    pub generated_normals: bool,
+}
+
+// Geometría indexada de una malla durante la importación. Las normales y coordenadas de
+// textura están vacías o tienen una por vértice.
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub texcoords: Vec<Vec2>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    // Aplica una rotación o reflejo a posiciones y normales; si refleja, invierte el orden de
+    // los vértices de cada triángulo para que las caras sigan mirando hacia fuera
+    pub fn transform(&mut self, matrix: &Mat3) {
+        for position in &mut self.positions {
+            *position = matrix * *position;
+        }
+        for normal in &mut self.normals {
+            *normal = matrix * *normal;
+        }
+        if matrix.determinant() < 0.0 {
+            for tri in self.indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+
+    // Aplica la transformación de un nodo de la escena (traslación, rotación y escala, quizás
+    // no uniforme); las normales usan la inversa transpuesta para seguir perpendiculares
+    pub fn transform_affine(&mut self, matrix: &Mat4) {
+        for position in &mut self.positions {
+            *position = (matrix * Vec4::new(position.x, position.y, position.z, 1.0)).xyz();
+        }
+        let linear = matrix.fixed_view::<3, 3>(0, 0).into_owned();
+        let normal_matrix = linear.try_inverse().map(|inverse| inverse.transpose()).unwrap_or(linear);
+        for normal in &mut self.normals {
+            let transformed = normal_matrix * *normal;
+            if transformed.magnitude() > 1e-12 {
+                *normal = transformed.normalize();
+            }
+        }
+        if linear.determinant() < 0.0 {
+            for tri in self.indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+
+    // Une vértices, quita triángulos degenerados y completa las normales
+    pub fn process(&mut self, options: &ImportOptions) -> ProcessStats {
+        let generate = options.normals != NormalMode::FromFile || self.normals.len() != self.positions.len();
+        if generate {
+            // Las normales se van a rehacer: no deben impedir que se unan los vértices
+            self.normals.clear();
+        }
+        if self.texcoords.len() != self.positions.len() {
+            self.texcoords.clear();
+        }
+
+        let welded_vertices = if options.weld_tolerance > 0.0 { self.weld(options.weld_tolerance) } else { 0 };
+        let degenerate_triangles = self.remove_degenerate_triangles();
+        if generate {
+            match options.normals {
+                NormalMode::Flat => self.flat_normals(),
+                NormalMode::Smooth | NormalMode::FromFile => self.smooth_normals(options.crease_angle),
+            }
+        }
+
+        ProcessStats { welded_vertices, degenerate_triangles, generated_normals: generate }
+    }
+
+    // Une los vértices con la misma posición (dentro de la tolerancia) y los mismos atributos.
+    // Devuelve cuántos vértices se eliminaron.
+    pub fn weld(&mut self, tolerance: f32) -> usize {
+        let quantize = |v: f32, step: f32| (v / step).round() as i64;
+        let mut unique: HashMap<[i64; 8], u32> = HashMap::new();
+        let mut remap = Vec::with_capacity(self.positions.len());
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut texcoords = Vec::new();
+
+        for i in 0..self.positions.len() {
+            let p = self.positions[i];
+            let n = self.normals.get(i).copied().unwrap_or_else(Vec3::zeros);
+            let t = self.texcoords.get(i).copied().unwrap_or_else(Vec2::zeros);
+            let key = [
+                quantize(p.x, tolerance), quantize(p.y, tolerance), quantize(p.z, tolerance),
+                quantize(n.x, 1e-4), quantize(n.y, 1e-4), quantize(n.z, 1e-4),
+                quantize(t.x, 1e-5), quantize(t.y, 1e-5),
+            ];
+            let index = *unique.entry(key).or_insert_with(|| {
+                positions.push(p);
+                if !self.normals.is_empty() {
+                    normals.push(n);
+                }
+                if !self.texcoords.is_empty() {
+                    texcoords.push(t);
+                }
+                positions.len() as u32 - 1
+            });
+            remap.push(index);
+        }
+
+        let removed = self.positions.len() - positions.len();
+        for index in &mut self.indices {
+            *index = remap[*index as usize];
+        }
+        self.positions = positions;
+        self.normals = normals;
+        self.texcoords = texcoords;
+        removed
+    }
+
+    // Quita los triángulos con vértices repetidos o sin área. Devuelve cuántos se quitaron.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let positions = &self.positions;
+        let before = self.indices.len() / 3;
+        let indices: Vec<u32> = self.indices.chunks_exact(3)
+            .filter(|tri| {
+                if tri[0] == tri[1] || tri[1] == tri[2] || tri[0] == tri[2] {
+                    return false;
+                }
+                let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| positions[i as usize]);
+                let longest = (b - a).magnitude_squared().max((c - b).magnitude_squared()).max((a - c).magnitude_squared());
+                (b - a).cross(&(c - a)).magnitude() > longest * 1e-6
+            })
+            .flatten()
+            .copied()
+            .collect();
+        self.indices = indices;
+        before - self.indices.len() / 3
+    }
+
+    // Cada triángulo recibe vértices propios con la normal de su cara
+    pub fn flat_normals(&mut self) {
+        let mut positions = Vec::with_capacity(self.indices.len());
+        let mut normals = Vec::with_capacity(self.indices.len());
+        let mut texcoords = Vec::new();
+
+        for tri in self.indices.chunks_exact(3) {
+            let normal = face_normal(&self.positions, tri);
+            for &i in tri {
+                positions.push(self.positions[i as usize]);
+                normals.push(normal);
+                if !self.texcoords.is_empty() {
+                    texcoords.push(self.texcoords[i as usize]);
+                }
+            }
+        }
+
+        self.indices = (0..positions.len() as u32).collect();
+        self.positions = positions;
+        self.normals = normals;
+        self.texcoords = texcoords;
+    }
+
+    // Normales suaves ponderadas por el ángulo de cada esquina. Las caras vecinas se buscan
+    // por posición, así las costuras de UV no cortan el suavizado; las que forman un ángulo
+    // mayor que `crease_angle` no se mezclan y el vértice se duplica para mantener la arista.
+    pub fn smooth_normals(&mut self, crease_angle: f32) {
+        let triangles: Vec<[u32; 3]> = self.indices.chunks_exact(3).map(|tri| [tri[0], tri[1], tri[2]]).collect();
+        let face_normals: Vec<Vec3> = triangles.iter().map(|tri| face_normal(&self.positions, tri)).collect();
+
+        // Esquinas de todos los triángulos agrupadas por posición exacta
+        let mut corners_at: HashMap<[u32; 3], Vec<(usize, f32)>> = HashMap::new();
+        for (face, tri) in triangles.iter().enumerate() {
+            for corner in 0..3 {
+                let p = self.positions[tri[corner] as usize];
+                let e1 = self.positions[tri[(corner + 1) % 3] as usize] - p;
+                let e2 = self.positions[tri[(corner + 2) % 3] as usize] - p;
+                let angle = e1.normalize().dot(&e2.normalize()).clamp(-1.0, 1.0).acos();
+                corners_at.entry(position_key(&p)).or_default().push((face, angle));
+            }
+        }
+
+        let cos_crease = crease_angle.cos();
+        let mut positions = Vec::new();
+        let mut normals = Vec::new();
+        let mut texcoords = Vec::new();
+        let mut unique: HashMap<(u32, [i32; 3]), u32> = HashMap::new();
+        let mut indices = Vec::with_capacity(self.indices.len());
+
+        for (face, tri) in triangles.iter().enumerate() {
+            for &vertex in tri {
+                let p = self.positions[vertex as usize];
+                let mut normal = Vec3::zeros();
+                for &(other, angle) in &corners_at[&position_key(&p)] {
+                    if face_normals[face].dot(&face_normals[other]) >= cos_crease {
+                        normal += face_normals[other] * angle;
+                    }
+                }
+                let normal = if normal.magnitude() > 1e-12 { normal.normalize() } else { face_normals[face] };
+
+                // Vértices que terminan con la misma normal se comparten
+                let key = (vertex, [normal.x, normal.y, normal.z].map(|c| (c * 1e4).round() as i32));
+                let index = *unique.entry(key).or_insert_with(|| {
+                    positions.push(p);
+                    normals.push(normal);
+                    if !self.texcoords.is_empty() {
+                        texcoords.push(self.texcoords[vertex as usize]);
+                    }
+                    positions.len() as u32 - 1
+                });
+                indices.push(index);
+            }
+        }
+
+        self.positions = positions;
+        self.normals = normals;
+        self.texcoords = texcoords;
+        self.indices = indices;
+    }
+
+    // Tangentes por vértice a partir de las derivadas de las UV de cada triángulo (método de Lengyel)
+    pub fn tangents(&self) -> Vec<Vec4> {
+        let (vertices, normals, texcoords) = (&self.positions, &self.normals, &self.texcoords);
+        if texcoords.len() != vertices.len() || normals.len() != vertices.len() {
+            return Vec::new();
+        }
+
+        let mut tangents = vec![Vec3::zeros(); vertices.len()];
+        let mut bitangents = vec![Vec3::zeros(); vertices.len()];
+
+        for tri in self.indices.chunks_exact(3) {
+            let (i0, i1, i2) = (tri[0] as usize, tri[1] as usize, tri[2] as usize);
+            let edge1 = vertices[i1] - vertices[i0];
+            let edge2 = vertices[i2] - vertices[i0];
+            let duv1 = texcoords[i1] - texcoords[i0];
+            let duv2 = texcoords[i2] - texcoords[i0];
+
+            let det = duv1.x * duv2.y - duv2.x * duv1.y;
+            if det.abs() < 1e-8 {
+                continue;
+            }
+            let r = 1.0 / det;
+            let tangent = (edge1 * duv2.y - edge2 * duv1.y) * r;
+            let bitangent = (edge2 * duv1.x - edge1 * duv2.x) * r;
+
+            for &i in &[i0, i1, i2] {
+                tangents[i] += tangent;
+                bitangents[i] += bitangent;
+            }
+        }
+
+        (0..vertices.len()).map(|i| {
+            let normal = normals[i];
+            // Gram-Schmidt: la tangente se hace perpendicular a la normal
+            let tangent = tangents[i] - normal * normal.dot(&tangents[i]);
+            if tangent.magnitude() < 1e-6 {
+                return Vec4::new(0.0, 0.0, 0.0, 1.0);
+            }
+            let tangent = tangent.normalize();
+            let handedness = if normal.cross(&tangent).dot(&bitangents[i]) < 0.0 { -1.0 } else { 1.0 };
+            Vec4::new(tangent.x, tangent.y, tangent.z, handedness)
+        }).collect()
+    }
+}
+
+fn face_normal(positions: &[Vec3], tri: &[u32]) -> Vec3 {
+    let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| positions[i as usize]);
+    let normal = (b - a).cross(&(c - a));
+    if normal.magnitude() > 1e-12 { normal.normalize() } else { Vec3::new(0.0, 1.0, 0.0) }
+}
+
+fn position_key(p: &Vec3) -> [u32; 3] {
+    [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+}
+
+// Caja alineada a los ejes que envuelve un conjunto de puntos
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Vec3>) -> Self {
+        let mut bounds = Bounds { min: Vec3::repeat(f32::INFINITY), max: Vec3::repeat(f32::NEG_INFINITY) };
+        for point in points {
+            bounds.min = bounds.min.inf(point);
+            bounds.max = bounds.max.sup(point);
+        }
+        bounds
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x
+    }
+
+    pub fn size(&self) -> Vec3 {
+        if self.is_empty() { Vec3::zeros() } else { self.max - self.min }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        if self.is_empty() { Vec3::zeros() } else { (self.min + self.max) * 0.5 }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    // Esfera de Ritter: parte de los dos puntos más alejados sobre un eje y crece hasta
+    // contener todos; queda a pocos por ciento de la mínima
+    pub fn from_points(points: &[Vec3]) -> Self {
+        let Some(&first) = points.first() else {
+            return BoundingSphere { center: Vec3::zeros(), radius: 0.0 };
+        };
+        let farthest = |from: Vec3| *points.iter()
+            .max_by(|a, b| (*a - from).magnitude_squared().total_cmp(&(*b - from).magnitude_squared()))
+            .unwrap();
+        let a = farthest(first);
+        let b = farthest(a);
+
+        let mut center = (a + b) * 0.5;
+        let mut radius = (b - a).magnitude() * 0.5;
+        for point in points {
+            let distance = (point - center).magnitude();
+            if distance > radius {
+                let grow = (distance - radius) * 0.5;
+                radius += grow;
+                center += (point - center) / distance * grow;
+            }
+        }
+        BoundingSphere { center, radius }
+    }
+}
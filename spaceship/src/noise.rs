@@ -0,0 +1,101 @@
+use nalgebra_glm::Vec3;
+
+// Hash entero de una celda de la rejilla; evita tablas de permutación
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6b343)
+        ^ (y as u32).wrapping_mul(0xd8163841)
+        ^ (z as u32).wrapping_mul(0xcb1ab31f);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1e995);
+    h ^ (h >> 15)
+}
+
+// Uno de los 12 gradientes de Perlin (aristas de un cubo)
+fn gradient(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    match hash % 12 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Ruido de Perlin 3D, aproximadamente en [-1, 1]
+pub fn perlin(p: &Vec3) -> f32 {
+    let xi = p.x.floor();
+    let yi = p.y.floor();
+    let zi = p.z.floor();
+    let (x, y, z) = (p.x - xi, p.y - yi, p.z - zi);
+    let (xi, yi, zi) = (xi as i32, yi as i32, zi as i32);
+
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient(hash(xi + dx, yi + dy, zi + dz), x - dx as f32, y - dy as f32, z - dz as f32)
+    };
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), u);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), u);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), u);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), u);
+
+    lerp(lerp(x00, x10, v), lerp(x01, x11, v), w)
+}
+
+// Suma de octavas de Perlin (fractional Brownian motion); en la práctica casi todo
+// el resultado cae en [-0.4, 0.4]
+pub fn fbm(p: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut total = 0.0;
+
+    for _ in 0..octaves {
+        sum += perlin(&(p * frequency)) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+
+    sum / total
+}
+
+// Generador pseudoaleatorio pequeño (xorshift), reproducible a partir de una semilla
+pub struct SeedRng(u32);
+
+impl SeedRng {
+    pub fn new(seed: u32) -> Self {
+        // Mezclar la semilla para que semillas consecutivas den secuencias distintas
+        let mut state = seed.wrapping_mul(0x9e37_79b9) ^ 0x85eb_ca6b;
+        state ^= state >> 16;
+        SeedRng(state.max(1))
+    }
+
+    // Valor en [0, 1)
+    pub fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+}
@@ -1,95 +1,557 @@
-use tobj;
-use nalgebra_glm::{Vec2, Vec3};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::vertex::Vertex;
 use crate::color::Color;
+use crate::texture::{Texture, WrapMode};
+use crate::gltf_loader;
+use crate::mesh::{normalize_meshes, Axis, Bounds, Handedness, BoundingSphere, ImportOptions, ImportTransform, MeshData, NormalMode, ProcessStats};
 
 pub struct Obj {
     meshes: Vec<Mesh>,
+    report: LoadReport,
+    transform: ImportTransform,
+    // Archivos de los que salió el modelo (el propio, bibliotecas MTL, texturas, buffers)
+    sources: Vec<PathBuf>,
 }
 
+// Texturas ya cargadas por ruta; se comparte entre modelos para no leer dos veces la misma imagen
+pub type TextureCache = HashMap<PathBuf, Arc<Texture>>;
+
 struct Mesh {
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
+    tangents: Vec<Vec4>,
     indices: Vec<u32>,
-    material_color: Color,
+    material: Material,
 }
 
-impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, materials) = tobj::load_obj(
-            filename,
-            &tobj::LoadOptions {
-                single_index: true,
-                triangulate: true,
-                ignore_points: true,
-                ignore_lines: true,
-                ..Default::default()
+impl Mesh {
+    fn new(data: MeshData, material: Material) -> Self {
+        let tangents = data.tangents();
+        Mesh {
+            vertices: data.positions,
+            normals: data.normals,
+            texcoords: data.texcoords,
+            tangents,
+            indices: data.indices,
+            material,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Material {
+    pub diffuse_color: Color,
+    pub diffuse_texture: Option<Arc<Texture>>,
+    // Mapa de normales en espacio tangente (map_Bump / bump en el MTL)
+    pub normal_texture: Option<Arc<Texture>>,
+    // Parámetros PBR de los materiales glTF; los del MTL quedan en un plástico mate
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            diffuse_color: Color::new(128, 128, 128),
+            diffuse_texture: None,
+            normal_texture: None,
+            metallic: 0.0,
+            roughness: 1.0,
+        }
+    }
+}
+
+// Vértices de una malla listos para dibujar junto con el material que los acompaña
+pub struct DrawBatch {
+    pub vertices: Vec<Vertex>,
+    pub material: Material,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    NotFound(PathBuf),
+    Io(PathBuf, std::io::Error),
+    // Línea del OBJ (desde 1) donde está el error, si se pudo ubicar
+    Parse { line: Option<usize>, message: String },
+    // Biblioteca indicada por mtllib que no se pudo leer
+    MissingMaterialLibrary { path: PathBuf, line: Option<usize> },
+    // Material usado con usemtl que ninguna biblioteca define
+    MissingMaterial { name: String, line: usize },
+    // Malla sin ningún triángulo con área (o modelo sin mallas dibujables)
+    DegenerateMesh(String),
+    // Opciones de importación con los ejes arriba y adelante paralelos
+    InvalidAxes { up: Axis, forward: Axis },
+    // glTF/GLB inválido o con extensiones requeridas que no se soportan
+    Gltf(String),
+    // Parte del modelo que se ignora al cargarlo (animaciones, puntos, formatos de imagen...)
+    Unsupported(String),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::NotFound(path) => write!(f, "no existe el archivo {}", path.display()),
+            ObjError::Io(path, err) => write!(f, "error de lectura en {}: {}", path.display(), err),
+            ObjError::Parse { line: Some(line), message } => write!(f, "error de formato en la línea {}: {}", line, message),
+            ObjError::Parse { line: None, message } => write!(f, "error de formato: {}", message),
+            ObjError::MissingMaterialLibrary { path, line } => {
+                write!(f, "no se pudo cargar la biblioteca de materiales {}", path.display())?;
+                if let Some(line) = line {
+                    write!(f, " (línea {})", line)?;
+                }
+                write!(f, "; se usan materiales por defecto")
+            }
+            ObjError::MissingMaterial { name, line } => {
+                write!(f, "el material '{}' (línea {}) no está definido; se usa el material por defecto", name, line)
+            }
+            ObjError::DegenerateMesh(name) => write!(f, "la malla '{}' no tiene triángulos con área", name),
+            ObjError::InvalidAxes { up, forward } => {
+                write!(f, "los ejes arriba ({:?}) y adelante ({:?}) deben ser perpendiculares", up, forward)
             }
+            ObjError::Gltf(message) => write!(f, "glTF inválido: {}", message),
+            ObjError::Unsupported(what) => write!(f, "no soportado: {}", what),
+        }
+    }
+}
+
+// Resumen de lo que se cargó, más los problemas que no impidieron cargar el modelo
+pub struct LoadReport {
+    pub meshes: usize,
+    pub triangles: usize,
+    pub materials: Vec<String>,
+    pub bounds: Bounds,
+    pub bounding_sphere: BoundingSphere,
+    // Cambios del procesamiento de importación, sumados en todas las mallas
+    pub welded_vertices: usize,
+    pub degenerate_triangles: usize,
+    pub generated_normals: usize,
+    pub warnings: Vec<ObjError>,
+}
+
+impl LoadReport {
+    fn new(meshes: &[Mesh], materials: Vec<String>, stats: &[ProcessStats], warnings: Vec<ObjError>) -> Self {
+        let points: Vec<Vec3> = meshes.iter().flat_map(|mesh| mesh.vertices.iter().copied()).collect();
+        LoadReport {
+            meshes: meshes.len(),
+            triangles: meshes.iter().map(|mesh| mesh.indices.len() / 3).sum(),
+            materials,
+            bounds: Bounds::from_points(&points),
+            bounding_sphere: BoundingSphere::from_points(&points),
+            welded_vertices: stats.iter().map(|stats| stats.welded_vertices).sum(),
+            degenerate_triangles: stats.iter().map(|stats| stats.degenerate_triangles).sum(),
+            generated_normals: stats.iter().filter(|stats| stats.generated_normals).count(),
+            warnings,
+        }
+    }
+}
+
+impl fmt::Display for LoadReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let materials = if self.materials.is_empty() { "ninguno".to_string() } else { self.materials.join(", ") };
+        let size = self.bounds.size();
+        write!(
+            f,
+            "{} mallas, {} triángulos, materiales: {}, tamaño {:.2} x {:.2} x {:.2}, esfera de radio {:.2} en ({:.2}, {:.2}, {:.2})",
+            self.meshes, self.triangles, materials, size.x, size.y, size.z,
+            self.bounding_sphere.radius, self.bounding_sphere.center.x, self.bounding_sphere.center.y, self.bounding_sphere.center.z
+        )?;
+        write!(
+            f,
+            "; {} vértices unidos, {} triángulos degenerados quitados, normales generadas en {} mallas",
+            self.welded_vertices, self.degenerate_triangles, self.generated_normals
         )?;
+        if !self.warnings.is_empty() {
+            write!(f, " ({} advertencias)", self.warnings.len())?;
+        }
+        Ok(())
+    }
+}
 
-        let materials = materials?;
+const LOAD_OPTIONS: tobj::LoadOptions = tobj::LoadOptions {
+    single_index: true,
+    triangulate: true,
+    ignore_points: true,
+    ignore_lines: true,
+};
 
-        let meshes = models.into_iter().map(|model| {
-            let mesh = model.mesh;
-            
-            // Obtener el color del material si existe
-            let material_color = if let Some(mat_id) = mesh.material_id {
-                if let Some(material) = materials.get(mat_id) {
-                    // Usar el color difuso (Kd) del material
-                    if let Some(diffuse) = &material.diffuse {
-                        Color::new(
-                            (diffuse[0] * 255.0) as u8,
-                            (diffuse[1] * 255.0) as u8,
-                            (diffuse[2] * 255.0) as u8,
-                        )
-                    } else {
-                        Color::new(128, 128, 128)
-                    }
-                } else {
-                    Color::new(128, 128, 128)
-                }
-            } else {
-                Color::new(128, 128, 128)
+impl Obj {
+    // Carga un modelo según la extensión del archivo: glTF (.gltf, .glb) u OBJ (el resto).
+    // Las texturas de los MTL se buscan primero en `textures` y las nuevas se agregan ahí.
+    pub fn load(filename: &str, options: &ImportOptions, textures: &mut TextureCache) -> Result<Self, ObjError> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "gltf" | "glb" => {
+                let scene = gltf_loader::load(filename)?;
+                Obj::build(filename, scene.parts, scene.material_names, scene.warnings, scene.sources, options)
+            }
+            _ => Obj::load_obj(filename, options, textures),
+        }
+    }
+
+    // Carga un OBJ con sus materiales y procesa cada malla según las opciones. Si falta la
+    // biblioteca MTL o un material, las mallas usan el material por defecto y el problema
+    // queda en las advertencias del reporte.
+    fn load_obj(filename: &str, options: &ImportOptions, texture_cache: &mut TextureCache) -> Result<Self, ObjError> {
+        let path = Path::new(filename);
+        let text = fs::read_to_string(path).map_err(|err| match err.kind() {
+            ErrorKind::NotFound => ObjError::NotFound(path.to_path_buf()),
+            _ => ObjError::Io(path.to_path_buf(), err),
+        })?;
+
+        // Las rutas del MTL y de sus texturas son relativas al archivo OBJ
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        let warnings = RefCell::new(Vec::new());
+        // También las bibliotecas que faltan: si aparecen, el modelo se puede recargar
+        let sources = RefCell::new(vec![path.to_path_buf()]);
+        let (models, materials) = tobj::load_obj_buf(&mut text.as_bytes(), &LOAD_OPTIONS, |mtl_path| {
+            let mtl_path = base_dir.join(mtl_path);
+            sources.borrow_mut().push(mtl_path.clone());
+            tobj::load_mtl(&mtl_path).or_else(|_| {
+                let line = find_line(&text, |line| line.starts_with("mtllib"));
+                warnings.borrow_mut().push(ObjError::MissingMaterialLibrary { path: mtl_path, line });
+                Ok((Vec::new(), Default::default()))
+            })
+        }).map_err(|err| {
+            let (line, message) = match find_parse_error(&text) {
+                Some((line, message)) => (Some(line), message),
+                None => (None, err.to_string()),
             };
+            ObjError::Parse { line, message }
+        })?;
+        let mut warnings = warnings.into_inner();
+        let materials = materials.unwrap_or_default();
+
+        for (line, name) in material_references(&text) {
+            if !materials.iter().any(|material| material.name == name) {
+                warnings.push(ObjError::MissingMaterial { name, line });
+            }
+        }
 
-            Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], -v[1], -v[2]))
+        let mut sources = sources.into_inner();
+        let material_names = materials.iter().map(|material| material.name.clone()).collect();
+        let materials: Vec<Material> = materials.iter()
+            .map(|material| load_material(material, base_dir, texture_cache, &mut sources))
+            .collect();
+
+        let parts = models.into_iter().map(|model| {
+            let mesh = model.mesh;
+            let material = mesh.material_id
+                .and_then(|mat_id| materials.get(mat_id))
+                .cloned()
+                .unwrap_or_default();
+
+            let data = MeshData {
+                positions: mesh.positions.chunks(3)
+                    .map(|v| Vec3::new(v[0], v[1], v[2]))
                     .collect(),
                 normals: mesh.normals.chunks(3)
-                    .map(|n| Vec3::new(n[0], -n[1], -n[2]))
+                    .map(|n| Vec3::new(n[0], n[1], n[2]))
                     .collect(),
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
                 indices: mesh.indices,
-                material_color,
-            }
+            };
+            (model.name, data, material)
         }).collect();
 
-        Ok(Obj { meshes })
+        Obj::build(filename, parts, material_names, warnings, sources, options)
+    }
+
+    // Lleva las mallas a los ejes del motor, las procesa y normaliza el modelo completo
+    fn build(
+        name: &str,
+        parts: Vec<(String, MeshData, Material)>,
+        material_names: Vec<String>,
+        mut warnings: Vec<ObjError>,
+        sources: Vec<PathBuf>,
+        options: &ImportOptions,
+    ) -> Result<Self, ObjError> {
+        let axes = options.axis_matrix()
+            .ok_or(ObjError::InvalidAxes { up: options.up_axis, forward: options.forward_axis })?;
+
+        let mut datas = Vec::new();
+        let mut materials = Vec::new();
+        let mut stats = Vec::new();
+        for (mesh_name, mut data, material) in parts {
+            data.transform(&axes);
+            let mesh_stats = data.process(options);
+            if data.indices.is_empty() {
+                warnings.push(ObjError::DegenerateMesh(mesh_name));
+                continue;
+            }
+            datas.push(data);
+            materials.push(material);
+            stats.push(mesh_stats);
+        }
+        if datas.is_empty() {
+            return Err(ObjError::DegenerateMesh(name.to_string()));
+        }
+
+        let transform = normalize_meshes(&mut datas, axes, options);
+        let meshes: Vec<Mesh> = datas.into_iter().zip(materials)
+            .map(|(data, material)| Mesh::new(data, material))
+            .collect();
+
+        for warning in &warnings {
+            eprintln!("Advertencia ({}): {}", name, warning);
+        }
+        let report = LoadReport::new(&meshes, material_names, &stats, warnings);
+        Ok(Obj { meshes, report, transform, sources })
     }
 
-    pub fn get_vertex_array(&self) -> Vec<Vertex> {
-        let mut vertices = Vec::new();
+    // Nave sencilla de reemplazo para cuando no se puede cargar el modelo: un dardo de caras
+    // planas del mismo tamaño y orientación que el modelo original (+Y arriba, punta hacia +Z).
+    pub fn placeholder_ship(options: &ImportOptions) -> Self {
+        let points = [
+            Vec3::new(0.0, 1.0, 13.0),   // punta
+            Vec3::new(0.0, 3.0, -6.0),   // lomo
+            Vec3::new(0.0, -1.5, -6.0),  // panza
+            Vec3::new(-7.5, 0.5, -6.5),  // ala izquierda
+            Vec3::new(7.5, 0.5, -6.5),   // ala derecha
+            Vec3::new(0.0, 0.8, -8.0),   // tobera
+        ];
+        let faces = [[0, 3, 1], [0, 1, 4], [0, 2, 3], [0, 4, 2], [1, 3, 5], [1, 5, 4], [2, 5, 3], [2, 4, 5]];
+        let center = points.iter().sum::<Vec3>() / points.len() as f32;
+
+        let mut indices = Vec::new();
+        for [a, b, c] in faces {
+            // Orden de los vértices tal que la normal de cada cara apunte hacia fuera
+            let normal = (points[b] - points[a]).cross(&(points[c] - points[a]));
+            let outward = normal.dot(&((points[a] + points[b] + points[c]) / 3.0 - center)) > 0.0;
+            indices.extend(if outward { [a, b, c] } else { [a, c, b] }.map(|i| i as u32));
+        }
+        let data = MeshData {
+            positions: points.to_vec(),
+            normals: Vec::new(),
+            texcoords: Vec::new(),
+            indices,
+        };
+        let material = Material { diffuse_color: Color::new(40, 60, 110), ..Material::default() };
+        // Los ejes de la nave de reemplazo ya son los del motor; del resto de opciones sólo
+        // importan el pivote y el tamaño
+        let options = ImportOptions {
+            normals: NormalMode::Flat,
+            up_axis: Axis::PosY,
+            forward_axis: Axis::PosZ,
+            handedness: Handedness::Right,
+            ..*options
+        };
+        Obj::build("nave de reemplazo", vec![("nave".to_string(), data, material)], Vec::new(), Vec::new(), Vec::new(), &options)
+            .expect("la nave de reemplazo es una malla válida")
+    }
+
+    pub fn report(&self) -> &LoadReport {
+        &self.report
+    }
+
+    pub fn transform(&self) -> &ImportTransform {
+        &self.transform
+    }
+
+    pub fn sources(&self) -> &[PathBuf] {
+        &self.sources
+    }
+
+    pub fn get_draw_batches(&self) -> Vec<DrawBatch> {
+        self.meshes.iter().map(|mesh| {
+            let mut vertices = Vec::with_capacity(mesh.indices.len());
 
-        for mesh in &self.meshes {
             for &index in &mesh.indices {
                 let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
-                    .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+                // La importación siempre completa las normales
+                let normal = mesh.normals[index as usize];
                 let tex_coords = mesh.texcoords.get(index as usize)
                     .cloned()
                     .unwrap_or(Vec2::new(0.0, 0.0));
 
                 let mut vertex = Vertex::new(position, normal, tex_coords);
-                vertex.color = mesh.material_color;
+                vertex.color = mesh.material.diffuse_color;
+                if let Some(tangent) = mesh.tangents.get(index as usize) {
+                    vertex.tangent = *tangent;
+                }
                 vertices.push(vertex);
             }
+
+            DrawBatch {
+                vertices,
+                material: mesh.material.clone(),
+            }
+        }).collect()
+    }
+}
+
+fn load_material(
+    material: &tobj::Material,
+    base_dir: &Path,
+    texture_cache: &mut TextureCache,
+    sources: &mut Vec<PathBuf>,
+) -> Material {
+    // Usar el color difuso (Kd) del material
+    let diffuse_color = material.diffuse
+        .map(|diffuse| Color::from_float(diffuse[0], diffuse[1], diffuse[2]))
+        .unwrap_or(Material::default().diffuse_color);
+
+    let diffuse_texture = material.diffuse_texture.as_deref()
+        .and_then(|map| load_texture_map(map, base_dir, texture_cache, sources));
+    let normal_texture = material.normal_texture.as_deref()
+        .and_then(|map| load_texture_map(map, base_dir, texture_cache, sources));
+
+    Material {
+        diffuse_color,
+        diffuse_texture,
+        normal_texture,
+        ..Material::default()
+    }
+}
+
+// Carga la textura de una instrucción map_* del MTL; si falla se avisa y el material queda sin textura
+fn load_texture_map(
+    map: &str,
+    base_dir: &Path,
+    texture_cache: &mut TextureCache,
+    sources: &mut Vec<PathBuf>,
+) -> Option<Arc<Texture>> {
+    let (file, wrap) = parse_texture_map(map);
+    if file.is_empty() {
+        return None;
+    }
+
+    let path = base_dir.join(file.replace('\\', "/"));
+    if !sources.contains(&path) {
+        sources.push(path.clone());
+    }
+    if let Some(texture) = texture_cache.get(&path) {
+        return Some(texture.clone());
+    }
+
+    match Texture::load(&path) {
+        Ok(texture) => {
+            let texture = Arc::new(texture.with_wrap(wrap));
+            texture_cache.insert(path, texture.clone());
+            Some(texture)
+        }
+        Err(err) => {
+            eprintln!("Advertencia: no se pudo cargar la textura {}: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+// Separa las opciones (-clamp, -s, -o, -bm, ...) del nombre de archivo en una instrucción map_*
+fn parse_texture_map(map: &str) -> (String, WrapMode) {
+    let mut wrap = WrapMode::Repeat;
+    let mut tokens = map.split_whitespace().peekable();
+    let mut file_tokens = Vec::new();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "-clamp" => {
+                if tokens.next() == Some("on") {
+                    wrap = WrapMode::ClampToEdge;
+                }
+            }
+            "-blendu" | "-blendv" | "-cc" | "-texres" | "-imfchan" | "-bm" | "-boost" | "-type" => {
+                tokens.next();
+            }
+            "-mm" => {
+                tokens.next();
+                tokens.next();
+            }
+            "-o" | "-s" | "-t" => {
+                for _ in 0..3 {
+                    if tokens.peek().is_some_and(|value| value.parse::<f32>().is_ok()) {
+                        tokens.next();
+                    }
+                }
+            }
+            _ => file_tokens.push(token),
         }
+    }
 
-        vertices
+    (file_tokens.join(" "), wrap)
+}
+
+// Número de la primera línea (desde 1) que cumple la condición
+fn find_line(text: &str, matches: impl Fn(&str) -> bool) -> Option<usize> {
+    text.lines().position(|line| matches(line.trim())).map(|index| index + 1)
+}
+
+// Materiales usados con usemtl y la línea donde aparecen
+fn material_references(text: &str) -> Vec<(usize, String)> {
+    text.lines().enumerate()
+        .filter_map(|(index, line)| {
+            let name = line.trim().strip_prefix("usemtl")?.trim();
+            (!name.is_empty()).then(|| (index + 1, name.to_string()))
+        })
+        .collect()
+}
+
+// tobj no dice en qué línea falló; se repasan las instrucciones que puede rechazar para
+// ubicar la primera inválida
+fn find_parse_error(text: &str) -> Option<(usize, String)> {
+    let mut counts = [0usize; 3]; // v, vt, vn
+
+    for (index, line) in text.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+        let values: Vec<&str> = words.collect();
+        let numbers_ok = values.iter().all(|value| value.parse::<f32>().is_ok());
+
+        let error = match keyword {
+            "v" if values.len() < 3 || !numbers_ok => Some("vértice con coordenadas inválidas".to_string()),
+            "vt" if values.is_empty() || !numbers_ok => Some("coordenada de textura inválida".to_string()),
+            "vn" if values.len() < 3 || !numbers_ok => Some("normal inválida".to_string()),
+            "f" if values.len() < 3 => Some("cara con menos de 3 vértices".to_string()),
+            "f" => values.iter().find_map(|corner| check_face_corner(corner, &counts)),
+            "usemtl" if values.is_empty() => Some("usemtl sin nombre de material".to_string()),
+            _ => None,
+        };
+        if let Some(message) = error {
+            return Some((index + 1, message));
+        }
+
+        match keyword {
+            "v" => counts[0] += 1,
+            "vt" => counts[1] += 1,
+            "vn" => counts[2] += 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Revisa una esquina de cara (v, v/vt, v//vn o v/vt/vn) contra los elementos ya definidos
+fn check_face_corner(corner: &str, counts: &[usize; 3]) -> Option<String> {
+    let names = ["vértice", "coordenada de textura", "normal"];
+    for (slot, index) in corner.split('/').enumerate() {
+        if slot > 2 {
+            return Some(format!("esquina de cara inválida '{}'", corner));
+        }
+        if index.is_empty() && slot > 0 {
+            continue;
+        }
+        let Ok(index) = index.parse::<i64>() else {
+            return Some(format!("índice inválido en la esquina '{}'", corner));
+        };
+        // Los índices negativos cuentan hacia atrás desde el último elemento definido
+        let count = counts[slot] as i64;
+        let resolved = if index < 0 { count + index + 1 } else { index };
+        if resolved < 1 || resolved > count {
+            return Some(format!("índice de {} fuera de rango ({} de {})", names[slot], index, count));
+        }
     }
+    None
 }
@@ -0,0 +1,255 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::noise::SeedRng;
+use crate::Uniforms;
+
+// Límite de partículas vivas; las que no caben simplemente no se emiten
+const MAX_PARTICLES: usize = 4000;
+// Radio máximo de un sprite en pantalla (píxeles), para que una partícula pegada a la
+// cámara no cubra media imagen
+const MAX_SPRITE_RADIUS: f32 = 48.0;
+
+// Cómo nacen y evolucionan las partículas de un emisor o de una ráfaga
+#[derive(Clone, Copy)]
+pub struct ParticleConfig {
+    // Partículas por segundo a intensidad 1 (sólo emisores continuos)
+    pub rate: f32,
+    // Vida en segundos, elegida al azar entre los dos valores
+    pub lifetime: (f32, f32),
+    // Rapidez de salida en unidades por segundo, elegida al azar entre los dos valores
+    pub speed: (f32, f32),
+    // Semiángulo del cono de salida alrededor de la dirección del emisor (radianes)
+    pub spread: f32,
+    // Frenado proporcional a la velocidad (1/s)
+    pub drag: f32,
+    // Color al nacer, a media vida y al morir (canales en [0, 1]); el brillo se apaga al final
+    pub colors: [Vec3; 3],
+    // Tamaño en unidades del mundo al nacer y al morir
+    pub size: (f32, f32),
+}
+
+impl ParticleConfig {
+    // Chorro del motor: gas muy caliente que se enfría a naranja y se disipa enseguida
+    pub fn engine_exhaust() -> Self {
+        ParticleConfig {
+            rate: 180.0,
+            lifetime: (0.15, 0.3),
+            speed: (3.0, 4.5),
+            spread: 0.12,
+            drag: 1.0,
+            colors: [Vec3::new(0.75, 0.85, 1.0), Vec3::new(1.0, 0.55, 0.15), Vec3::new(0.5, 0.12, 0.05)],
+            size: (0.04, 0.1),
+        }
+    }
+
+    // Bola de fuego: sale en todas direcciones, se frena y se vuelve humo rojizo
+    pub fn explosion() -> Self {
+        ParticleConfig {
+            rate: 0.0,
+            lifetime: (0.5, 1.1),
+            speed: (1.0, 3.0),
+            spread: PI,
+            drag: 2.5,
+            colors: [Vec3::new(1.0, 0.9, 0.6), Vec3::new(1.0, 0.4, 0.1), Vec3::new(0.25, 0.08, 0.04)],
+            size: (0.15, 0.45),
+        }
+    }
+
+    // Chispas y fragmentos pequeños que siguen su trayectoria casi sin frenarse
+    pub fn debris() -> Self {
+        ParticleConfig {
+            rate: 0.0,
+            lifetime: (1.0, 2.0),
+            speed: (2.0, 5.0),
+            spread: PI,
+            drag: 0.2,
+            colors: [Vec3::new(1.0, 0.8, 0.5), Vec3::new(0.7, 0.35, 0.15), Vec3::new(0.3, 0.2, 0.15)],
+            size: (0.03, 0.02),
+        }
+    }
+
+    // Cola de cometa: polvo y gas empujados en sentido opuesto al sol
+    #[allow(dead_code)] // El sistema por defecto no tiene cometas
+    pub fn comet_tail() -> Self {
+        ParticleConfig {
+            rate: 150.0,
+            lifetime: (2.0, 3.5),
+            speed: (0.6, 1.2),
+            spread: 0.25,
+            drag: 0.0,
+            colors: [Vec3::new(0.7, 0.85, 1.0), Vec3::new(0.4, 0.55, 0.8), Vec3::new(0.15, 0.2, 0.3)],
+            size: (0.08, 0.4),
+        }
+    }
+}
+
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+    config: ParticleConfig,
+}
+
+// Emisor continuo fijo a un punto de un objeto, en las coordenadas locales de su modelo
+pub struct Emitter {
+    pub config: ParticleConfig,
+    pub offset: Vec3,
+    pub direction: Vec3,
+    // Multiplica la tasa de emisión, la rapidez y el tamaño; 0 apaga el emisor
+    pub intensity: f32,
+    // Fracción de partícula pendiente de emitir entre cuadros
+    pending: f32,
+}
+
+impl Emitter {
+    pub fn new(config: ParticleConfig, offset: Vec3, direction: Vec3) -> Self {
+        Emitter { config, offset, direction, intensity: 0.0, pending: 0.0 }
+    }
+}
+
+// Partículas simuladas en la CPU y dibujadas como sprites aditivos con prueba de profundidad
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: SeedRng,
+}
+
+impl ParticleSystem {
+    pub fn new(seed: u32) -> Self {
+        ParticleSystem { particles: Vec::new(), rng: SeedRng::new(seed) }
+    }
+
+    // Emite las partículas que le tocan a un emisor en este cuadro. `model_matrix` coloca el
+    // emisor en el mundo y `base_velocity` es la velocidad del objeto, que heredan las partículas.
+    pub fn emit(&mut self, emitter: &mut Emitter, model_matrix: &Mat4, base_velocity: Vec3, delta_time: f32) {
+        if emitter.intensity <= 0.0 {
+            emitter.pending = 0.0;
+            return;
+        }
+        emitter.pending += emitter.config.rate * emitter.intensity * delta_time;
+
+        let origin = (model_matrix * Vec4::new(emitter.offset.x, emitter.offset.y, emitter.offset.z, 1.0)).xyz();
+        let direction = (model_matrix * Vec4::new(emitter.direction.x, emitter.direction.y, emitter.direction.z, 0.0)).xyz();
+        if direction.magnitude() < 1e-6 {
+            return;
+        }
+        let direction = direction.normalize();
+
+        while emitter.pending >= 1.0 {
+            emitter.pending -= 1.0;
+            // Repartir las partículas a lo largo del cuadro para que el chorro no salga a golpes
+            let step = self.rng.next() * delta_time;
+            let mut config = emitter.config;
+            config.size = (config.size.0 * emitter.intensity.sqrt(), config.size.1 * emitter.intensity.sqrt());
+            self.spawn(&config, origin - base_velocity * step, direction, base_velocity, emitter.intensity.sqrt(), step);
+        }
+    }
+
+    // Ráfaga de `count` partículas desde un punto (explosiones, restos de un choque)
+    pub fn burst(&mut self, config: &ParticleConfig, position: Vec3, direction: Vec3, base_velocity: Vec3, count: usize) {
+        let direction = if direction.magnitude() > 1e-6 { direction.normalize() } else { Vec3::new(0.0, 1.0, 0.0) };
+        for _ in 0..count {
+            self.spawn(config, position, direction, base_velocity, 1.0, 0.0);
+        }
+    }
+
+    fn spawn(&mut self, config: &ParticleConfig, position: Vec3, direction: Vec3, base_velocity: Vec3, speed_scale: f32, age: f32) {
+        if self.particles.len() >= MAX_PARTICLES {
+            return;
+        }
+        let heading = self.random_in_cone(direction, config.spread);
+        let speed = self.rng.range(config.speed.0, config.speed.1) * speed_scale;
+        let lifetime = self.rng.range(config.lifetime.0, config.lifetime.1);
+        let velocity = base_velocity + heading * speed;
+
+        self.particles.push(Particle {
+            position: position + velocity * age,
+            velocity,
+            age,
+            lifetime,
+            config: *config,
+        });
+    }
+
+    // Dirección al azar dentro de un cono de semiángulo `spread` (uniforme sobre el casquete)
+    fn random_in_cone(&mut self, axis: Vec3, spread: f32) -> Vec3 {
+        let cos_theta = 1.0 - self.rng.next() * (1.0 - spread.cos());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = self.rng.next() * 2.0 * PI;
+
+        let reference = if axis.y.abs() < 0.99 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let tangent = reference.cross(&axis).normalize();
+        let bitangent = axis.cross(&tangent);
+        axis * cos_theta + (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for particle in &mut self.particles {
+            particle.velocity *= (1.0 - particle.config.drag * delta_time).max(0.0);
+            particle.position += particle.velocity * delta_time;
+            particle.age += delta_time;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+    }
+
+    // Cada partícula es un disco difuso en pantalla cuyo tamaño depende de la distancia.
+    // Se suma a la imagen sin escribir profundidad, así que va después de la geometría opaca.
+    pub fn render(&self, framebuffer: &mut Framebuffer, uniforms: &Uniforms) {
+        let view_projection = uniforms.projection_matrix * uniforms.view_matrix;
+        let pixels_per_unit = uniforms.projection_matrix[(1, 1)] * uniforms.viewport_matrix[(1, 1)].abs();
+
+        for particle in &self.particles {
+            let clip = view_projection * Vec4::new(particle.position.x, particle.position.y, particle.position.z, 1.0);
+            if clip.w <= 1e-3 {
+                continue;
+            }
+            let depth = clip.z / clip.w;
+            if depth.abs() > 1.0 {
+                continue;
+            }
+            let screen = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, depth, 1.0);
+
+            let life = (particle.age / particle.lifetime).clamp(0.0, 1.0);
+            let size = particle.config.size.0 + (particle.config.size.1 - particle.config.size.0) * life;
+            let radius = (size * pixels_per_unit / clip.w).min(MAX_SPRITE_RADIUS);
+            let color = color_over_life(&particle.config.colors, life) * (1.0 - life).powf(1.5);
+            // Las partículas de menos de un píxel reparten su luz en ese píxel
+            let coverage = (radius * radius).min(1.0);
+            let radius = radius.max(0.5);
+
+            let min_x = (screen.x - radius).floor().max(0.0) as usize;
+            let max_x = (screen.x + radius).ceil().min(framebuffer.width as f32 - 1.0);
+            let min_y = (screen.y - radius).floor().max(0.0) as usize;
+            let max_y = (screen.y + radius).ceil().min(framebuffer.height as f32 - 1.0);
+            if max_x < 0.0 || max_y < 0.0 {
+                continue;
+            }
+
+            for y in min_y..=max_y as usize {
+                for x in min_x..=max_x as usize {
+                    let dx = (x as f32 + 0.5 - screen.x) / radius;
+                    let dy = (y as f32 + 0.5 - screen.y) / radius;
+                    let distance_squared = dx * dx + dy * dy;
+                    if distance_squared > 1.0 {
+                        continue;
+                    }
+                    // Caída gaussiana hacia el borde del disco
+                    let weight = (-3.0 * distance_squared).exp() * coverage;
+                    let light = color * weight;
+                    framebuffer.add_point(x, y, depth, Color::from_float(light.x, light.y, light.z));
+                }
+            }
+        }
+    }
+}
+
+// Interpolación entre los tres colores de la vida de una partícula
+fn color_over_life(colors: &[Vec3; 3], life: f32) -> Vec3 {
+    if life < 0.5 {
+        colors[0].lerp(&colors[1], life * 2.0)
+    } else {
+        colors[1].lerp(&colors[2], life * 2.0 - 1.0)
+    }
+}
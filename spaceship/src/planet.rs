@@ -0,0 +1,197 @@
+use nalgebra_glm::Vec3;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::noise::{fbm, perlin, SeedRng};
+use crate::atmosphere::Atmosphere;
+use crate::clouds::CloudLayer;
+use crate::fragment_shader::{bump_direction, light_direction, ocean_specular, sun_facing, surface_at, surface_lighting, surface_normal, smoothstep};
+use crate::bake::Surface;
+use crate::Uniforms;
+
+// Colores de la superficie según la latitud y la altura; canales en [0, 1]
+#[derive(Clone, Copy)]
+pub struct Biomes {
+    pub deep_water: Vec3,
+    pub shallow_water: Vec3,
+    pub beach: Vec3,
+    // Tierras bajas cerca del ecuador, en latitudes medias y cerca de los polos
+    pub equator: Vec3,
+    pub temperate: Vec3,
+    pub tundra: Vec3,
+    pub mountain: Vec3,
+    pub snow: Vec3,
+}
+
+// Planeta procedural: todo su aspecto se deriva de estos parámetros, y `from_seed`
+// los elige a partir de una semilla
+#[derive(Clone, Copy)]
+pub struct PlanetParams {
+    pub seed: u32,
+    // Altura del nivel del mar entre -1 (sin océanos) y 1 (todo océano)
+    pub sea_level: f32,
+    // Detalle fino y montañas del relieve, entre 0 (suave) y 1 (abrupto)
+    pub roughness: f32,
+    // Frecuencia de los continentes sobre la esfera unitaria
+    pub continent_scale: f32,
+    pub biomes: Biomes,
+    // Fracción de la altura de la esfera cubierta por cada casquete polar
+    pub polar_cap: f32,
+    // Fracción del cielo cubierta por nubes; 0 = sin capa de nubes
+    pub cloud_coverage: f32,
+    // Color de dispersión de la atmósfera y su densidad; densidad 0 = sin atmósfera
+    pub atmosphere_color: Vec3,
+    pub atmosphere_density: f32,
+}
+
+impl PlanetParams {
+    // Un planeta verosímil por semilla: casi siempre agua azul y vegetación verde, a veces
+    // mundos áridos y de vez en cuando paletas alienígenas
+    pub fn from_seed(seed: u32) -> Self {
+        let mut rng = SeedRng::new(seed);
+        let alien = rng.next() < 0.2;
+        let arid = rng.next().powi(2);
+
+        let water_hue = if alien { rng.next() } else { rng.range(0.52, 0.64) };
+        let plant_hue = if alien { rng.next() } else { rng.range(0.2, 0.36) };
+        let sand = hsv(rng.range(0.08, 0.12), rng.range(0.3, 0.5), rng.range(0.7, 0.85));
+        let rock = hsv(rng.range(0.05, 0.1), rng.range(0.1, 0.3), rng.range(0.3, 0.45));
+        let jungle = hsv(plant_hue, rng.range(0.55, 0.75), rng.range(0.3, 0.45));
+        let forest = hsv(plant_hue + 0.04, rng.range(0.4, 0.6), rng.range(0.25, 0.4));
+
+        let biomes = Biomes {
+            deep_water: hsv(water_hue, 0.85, rng.range(0.25, 0.4)),
+            shallow_water: hsv(water_hue - 0.03, 0.65, rng.range(0.5, 0.65)),
+            beach: sand,
+            equator: jungle.lerp(&sand, arid),
+            temperate: forest.lerp(&rock, arid * 0.6),
+            tundra: hsv(rng.range(0.08, 0.2), 0.2, rng.range(0.45, 0.6)),
+            mountain: rock,
+            snow: Vec3::new(0.94, 0.95, 0.98),
+        };
+
+        let atmosphere_hue = if alien { rng.next() } else { rng.range(0.55, 0.65) };
+        // Los mundos áridos tienen menos agua, menos nubes y casquetes más pequeños
+        PlanetParams {
+            seed,
+            sea_level: rng.range(-0.5, 0.4) - arid * 0.5,
+            roughness: rng.range(0.2, 1.0),
+            continent_scale: rng.range(1.2, 3.0),
+            biomes,
+            polar_cap: rng.range(0.0, 0.3) * (1.0 - arid),
+            cloud_coverage: (rng.range(-0.15, 0.65) * (1.0 - arid)).max(0.0),
+            atmosphere_color: hsv(atmosphere_hue, rng.range(0.6, 0.85), 1.0),
+            atmosphere_density: rng.range(0.0, 1.4),
+        }
+    }
+
+    // Otra variante del planeta: la semilla siguiente de la secuencia
+    pub fn randomized(&self) -> Self {
+        PlanetParams::from_seed(self.seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223))
+    }
+
+    pub fn atmosphere(&self) -> Option<Atmosphere> {
+        (self.atmosphere_density > 0.15).then_some(Atmosphere {
+            color: self.atmosphere_color,
+            density: self.atmosphere_density,
+            thickness: 0.12,
+            mie: 0.3,
+        })
+    }
+
+    pub fn clouds(&self) -> Option<CloudLayer> {
+        (self.cloud_coverage > 0.05).then(|| CloudLayer {
+            coverage: self.cloud_coverage,
+            ..CloudLayer::earth_like()
+        })
+    }
+
+    // Desplazamiento del ruido propio de cada semilla: otra región del mismo campo infinito
+    fn noise_offset(&self) -> Vec3 {
+        let mut rng = SeedRng::new(self.seed ^ 0x5bd1_e995);
+        Vec3::new(rng.range(-500.0, 500.0), rng.range(-500.0, 500.0), rng.range(-500.0, 500.0))
+    }
+
+    // Altura del terreno en [-1, 1]: continentes, detalle fino y cordilleras sobre tierra firme
+    fn elevation(&self, direction: &Vec3, offset: &Vec3) -> f32 {
+        let p = direction * self.continent_scale + offset;
+        let continents = (fbm(&p, 4) * 2.5).clamp(-1.0, 1.0);
+        let detail = fbm(&(p * 3.0), 4) * 2.5;
+        let ridges = (1.0 - perlin(&(p * 3.0)).abs() * 2.0).max(0.0).powi(2);
+        let inland = smoothstep(0.0, 0.3, continents - self.sea_level);
+        (continents + detail * 0.15 * self.roughness + ridges * 0.5 * self.roughness * inland).clamp(-1.0, 1.0)
+    }
+}
+
+// Color a partir de tono, saturación y valor, todos en [0, 1] (el tono da la vuelta)
+fn hsv(hue: f32, saturation: f32, value: f32) -> Vec3 {
+    let hue = hue.rem_euclid(1.0) * 6.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Vec3::repeat(value - chroma) + Vec3::new(r, g, b)
+}
+
+pub fn procedural_planet_shader(fragment: &Fragment, uniforms: &Uniforms, params: &PlanetParams, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+    let surface = surface_at(fragment, uniforms, vertex_position, |position| procedural_surface(params, position));
+    let normal = surface_normal(uniforms, &surface);
+    let light_dir = light_direction(fragment, uniforms);
+    let facing = sun_facing(vertex_normal, &light_dir);
+    let intensity = surface_lighting(uniforms, &normal, &light_dir, facing);
+
+    let mut lit = surface.albedo.component_mul(&intensity);
+    // El destello depende de la cámara: se calcula siempre en el momento
+    if surface.mask > 0.5 {
+        let glint = ocean_specular(fragment, uniforms, &normal, &light_dir) * smoothstep(0.0, 0.1, facing);
+        lit += uniforms.light_color * glint;
+    }
+
+    Color::from_float(lit.x, lit.y, lit.z)
+}
+
+// Superficie de un planeta procedural: océanos por debajo del nivel del mar, biomas por
+// latitud y altura, y casquetes polares que cubren tierra y mar. La máscara marca el agua
+// descubierta, que refleja el sol.
+pub fn procedural_surface(params: &PlanetParams, position: &Vec3) -> Surface {
+    let direction = position.normalize();
+    let offset = params.noise_offset();
+    let height = params.elevation(&direction, &offset);
+    let biomes = &params.biomes;
+
+    // Borde irregular para las fronteras entre biomas
+    let jitter = fbm(&(direction * 6.0 + offset), 3) * 0.25;
+    let latitude = (direction.y.abs() + jitter).clamp(0.0, 1.0);
+    let underwater = height < params.sea_level;
+
+    let mut color = if underwater {
+        let depth = (params.sea_level - height) / (params.sea_level + 1.0).max(1e-3);
+        biomes.shallow_water.lerp(&biomes.deep_water, smoothstep(0.0, 0.3, depth))
+    } else {
+        let altitude = (height - params.sea_level) / (1.0 - params.sea_level).max(1e-3);
+        let lowland = biomes.equator
+            .lerp(&biomes.temperate, smoothstep(0.2, 0.45, latitude))
+            .lerp(&biomes.tundra, smoothstep(0.55, 0.75, latitude));
+        let land = biomes.beach
+            .lerp(&lowland, smoothstep(0.0, 0.04, altitude))
+            .lerp(&biomes.mountain, smoothstep(0.45, 0.65, altitude));
+        // La nieve baja de las cumbres hacia los polos
+        land.lerp(&biomes.snow, smoothstep(0.8, 0.9, altitude + latitude * 0.3))
+    };
+    let cap = smoothstep(1.0 - params.polar_cap, 1.0 - params.polar_cap + 0.03, latitude);
+    color = color.lerp(&biomes.snow, cap);
+
+    // El relieve sólo existe en tierra firme; el mar queda liso
+    let sea_level = params.sea_level;
+    let terrain = |p: &Vec3| params.elevation(&p.normalize(), &offset).max(sea_level);
+    Surface {
+        albedo: color,
+        normal: bump_direction(position, terrain, 0.02 + 0.03 * params.roughness),
+        mask: if underwater && cap < 0.5 { 1.0 } else { 0.0 },
+    }
+}
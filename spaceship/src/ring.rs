@@ -1,5 +1,11 @@
 use crate::vertex::Vertex;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::noise::perlin;
+use crate::fragment_shader::smoothstep;
+use crate::Uniforms;
 use nalgebra_glm::{Vec2, Vec3};
+use std::sync::Arc;
 
 pub fn create_ring(inner_radius: f32, outer_radius: f32, segments: u32) -> Vec<Vertex> {
     let mut vertices = Vec::new();
@@ -33,3 +39,169 @@ pub fn create_ring(inner_radius: f32, outer_radius: f32, segments: u32) -> Vec<V
     
     vertices
 }
+
+// Tramo del perfil radial de un sistema de anillos; los radios van en radios del planeta
+#[derive(Clone, Copy)]
+pub struct RingBand {
+    pub start: f32,
+    pub end: f32,
+    // Profundidad óptica vista de frente: 0 es vacío, por encima de 1 casi opaco
+    pub density: f32,
+    pub color: Vec3,
+}
+
+// Anillos definidos por un perfil radial de bandas; lo que no cubre ninguna banda es un hueco
+#[derive(Clone)]
+pub struct RingSystem {
+    bands: Arc<[RingBand]>,
+}
+
+// Ancho del borde suave de cada banda, en radios del planeta
+const BAND_EDGE: f32 = 0.006;
+
+impl RingSystem {
+    pub fn new(bands: Vec<RingBand>) -> Self {
+        RingSystem { bands: bands.into() }
+    }
+
+    // Perfil inspirado en Saturno: anillo C tenue, anillo B denso, división de Cassini,
+    // anillo A y la división de Encke cerca de su borde
+    pub fn saturn_like() -> Self {
+        let band = |start, end, density, color| RingBand { start, end, density, color };
+        RingSystem::new(vec![
+            band(1.30, 1.45, 0.12, Vec3::new(0.55, 0.5, 0.45)),
+            band(1.45, 1.72, 1.4, Vec3::new(0.92, 0.85, 0.72)),
+            band(1.72, 1.78, 0.03, Vec3::new(0.6, 0.58, 0.55)),
+            band(1.78, 1.93, 0.6, Vec3::new(0.85, 0.8, 0.7)),
+            band(1.95, 2.0, 0.45, Vec3::new(0.8, 0.76, 0.68)),
+        ])
+    }
+
+    pub fn inner_radius(&self) -> f32 {
+        self.bands.iter().map(|band| band.start).fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn outer_radius(&self) -> f32 {
+        self.bands.iter().map(|band| band.end).fold(0.0, f32::max)
+    }
+
+    // Profundidad óptica y color de las partículas a una distancia del centro del planeta
+    pub fn sample(&self, radius: f32) -> (f32, Vec3) {
+        let Some(band) = self.bands.iter().find(|band| radius >= band.start && radius < band.end) else {
+            return (0.0, Vec3::zeros());
+        };
+
+        let edge = smoothstep(band.start, band.start + BAND_EDGE, radius)
+            * (1.0 - smoothstep(band.end - BAND_EDGE, band.end, radius));
+        // Estructura fina: miles de anillitos con densidades distintas
+        let ringlets = perlin(&Vec3::new(radius * 90.0, 0.37, 0.71)) * 0.8
+            + perlin(&Vec3::new(radius * 400.0, 0.53, 0.19)) * 0.4;
+        let density = band.density * edge * (1.0 + ringlets).max(0.1);
+        let color = band.color * (1.0 + ringlets * 0.15);
+
+        (density, color)
+    }
+}
+
+// Estado de los anillos de un cuerpo para proyectar su sombra sobre el planeta
+#[derive(Clone)]
+pub struct RingShadow {
+    pub rings: RingSystem,
+    // Normal del plano de los anillos en espacio mundo
+    pub normal: Vec3,
+}
+
+// Anillos translúcidos: la luz reflejada en la cara iluminada, la que los atraviesa en la
+// cara opuesta (con dispersión hacia adelante al mirar hacia el sol) y la sombra del planeta.
+// El modelo de los anillos comparte centro y escala con el planeta.
+pub fn rings_shader(fragment: &Fragment, uniforms: &Uniforms, rings: &RingSystem, vertex_position: &Vec3) -> (Color, f32) {
+    let radius = Vec2::new(vertex_position.x, vertex_position.z).magnitude();
+    let (density, albedo) = rings.sample(radius);
+    if density <= 0.0 {
+        return (Color::black(), 0.0);
+    }
+
+    let center = uniforms.model_matrix.column(3).xyz();
+    let planet_radius = uniforms.model_matrix.column(0).xyz().magnitude();
+    let normal = uniforms.model_matrix.column(1).xyz().normalize();
+
+    let to_light = uniforms.light_position - fragment.world_position;
+    let to_camera = uniforms.camera_position - fragment.world_position;
+    if to_light.magnitude() < 1e-4 || to_camera.magnitude() < 1e-4 {
+        return (Color::black(), 0.0);
+    }
+    let light_dir = to_light.normalize();
+    let view_dir = to_camera.normalize();
+
+    // Los rayos rasantes atraviesan más material pero reparten la luz en más superficie
+    let mu_light = normal.dot(&light_dir).abs().max(0.02);
+    let mu_view = normal.dot(&view_dir).abs().max(0.02);
+    let opacity = 1.0 - (-density / mu_view).exp();
+
+    // Dispersión simple en una capa plana de partículas: en la cara iluminada se ve la luz
+    // reflejada y en la opuesta la que atraviesa el anillo
+    let lit_side = normal.dot(&light_dir) * normal.dot(&view_dir) > 0.0;
+    let scattered = if lit_side {
+        mu_light / (mu_light + mu_view) * (1.0 - (-density * (1.0 / mu_light + 1.0 / mu_view)).exp())
+    } else if (mu_view - mu_light).abs() > 1e-3 {
+        mu_light / (mu_view - mu_light) * ((-density / mu_view).exp() - (-density / mu_light).exp())
+    } else {
+        density / mu_light * (-density / mu_light).exp()
+    };
+
+    // Henyey-Greenstein respecto a la dispersión isótropa: a contraluz el polvo fino brilla
+    // mucho más que mirando con el sol a la espalda
+    let phase = if lit_side {
+        1.0
+    } else {
+        let cos_theta = -view_dir.dot(&light_dir);
+        let g: f32 = 0.7;
+        (1.0 - g * g) / (1.0 + g * g - 2.0 * g * cos_theta).powf(1.5)
+    };
+
+    let shadow = planet_shadow(&fragment.world_position, &light_dir, &center, planet_radius);
+    let light = uniforms.light_color * (scattered * phase * shadow * 2.0);
+    let radiance = albedo.component_mul(&light) + albedo * 0.02;
+
+    // La mezcla multiplica por la opacidad; se compensa para que la luz dispersada por
+    // anillos tenues no desaparezca con ella
+    let color = radiance / opacity.max(0.05);
+    (Color::from_float(color.x, color.y, color.z), opacity)
+}
+
+// Factor de luz (1 = sin sombra) en un punto del planeta según los anillos entre él y el sol
+pub fn ring_shadow_factor(fragment: &Fragment, uniforms: &Uniforms, shadow: &RingShadow) -> f32 {
+    let center = uniforms.model_matrix.column(3).xyz();
+    let planet_radius = uniforms.model_matrix.column(0).xyz().magnitude();
+    let light_dir = (uniforms.light_position - fragment.world_position).normalize();
+    let offset = fragment.world_position - center;
+
+    // Intersección del rayo hacia el sol con el plano de los anillos
+    let along = light_dir.dot(&shadow.normal);
+    if along.abs() < 1e-4 {
+        return 1.0;
+    }
+    let t = -offset.dot(&shadow.normal) / along;
+    if t <= 0.0 {
+        return 1.0;
+    }
+    let radius = (offset + light_dir * t).magnitude() / planet_radius;
+    let (density, _) = shadow.rings.sample(radius);
+
+    // En el lado nocturno no hay sol que proyecte sombras
+    let daylight = smoothstep(-0.1, 0.1, offset.normalize().dot(&light_dir));
+    1.0 - (1.0 - (-density / along.abs()).exp()) * daylight
+}
+
+// Sombra del planeta sobre un punto: 0 dentro de la sombra, 1 a plena luz, con penumbra
+fn planet_shadow(point: &Vec3, light_dir: &Vec3, center: &Vec3, planet_radius: f32) -> f32 {
+    let offset = center - point;
+    let t = offset.dot(light_dir);
+    if t <= 0.0 {
+        return 1.0;
+    }
+    // Distancia entre el centro del planeta y el rayo hacia el sol
+    let closest = (offset - light_dir * t).magnitude();
+    let penumbra = planet_radius * 0.04;
+    smoothstep(planet_radius - penumbra, planet_radius + penumbra, closest)
+}
@@ -0,0 +1,673 @@
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+use crate::atmosphere::Atmosphere;
+use crate::clouds::CloudLayer;
+use crate::color::Color;
+use crate::fragment_shader::ShaderType;
+use crate::mesh::{Axis, Handedness, ImportOptions, NormalMode, Pivot};
+use crate::planet::PlanetParams;
+use crate::ring::{RingBand, RingSystem};
+use crate::star::Star;
+use crate::{CelestialBody, SolarSystem};
+
+// Escena que se carga si no se indica otra; también va incluida en el ejecutable
+pub const DEFAULT_SCENE: &str = "sistema_solar.toml";
+pub const BUILTIN_SCENE: &str = include_str!("../assets/sistema_solar.toml");
+
+// Sistema solar y nave descritos en un archivo TOML (ver `assets/sistema_solar.toml`)
+pub struct Scene {
+    pub system: SolarSystem,
+    pub ship: ShipSpawn,
+}
+
+pub struct ShipSpawn {
+    // Modelo, relativo a la carpeta de recursos
+    pub model: String,
+    pub position: Vec3,
+    // Rumbo inicial en radianes alrededor de +Y
+    pub yaw: f32,
+    // Ejes, mano, pivote y largo con los que se importa el modelo
+    pub import: ImportOptions,
+    // Tobera del motor en las coordenadas del archivo del modelo
+    pub engine_nozzle: Vec3,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    NotFound(PathBuf),
+    Io(PathBuf, std::io::Error),
+    // TOML mal formado o con campos de tipo incorrecto o desconocidos
+    Parse { source: String, message: String },
+    // Valores fuera de rango, nombres repetidos, presets desconocidos...
+    Invalid { source: String, errors: Vec<String> },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::NotFound(path) => write!(f, "no existe el archivo de escena {}", path.display()),
+            SceneError::Io(path, err) => write!(f, "error de lectura en {}: {}", path.display(), err),
+            SceneError::Parse { source, message } => write!(f, "error de formato en {}: {}", source, message.trim_end()),
+            SceneError::Invalid { source, errors } => {
+                write!(f, "la escena {} no es válida:", source)?;
+                for error in errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Scene {
+    pub fn load(path: &Path) -> Result<Self, SceneError> {
+        let text = fs::read_to_string(path).map_err(|err| match err.kind() {
+            ErrorKind::NotFound => SceneError::NotFound(path.to_path_buf()),
+            _ => SceneError::Io(path.to_path_buf(), err),
+        })?;
+        Scene::parse(&text, &path.display().to_string())
+    }
+
+    // `source` sólo se usa en los mensajes de error
+    pub fn parse(text: &str, source: &str) -> Result<Self, SceneError> {
+        let file: SceneFile = toml::from_str(text).map_err(|err| SceneError::Parse {
+            source: source.to_string(),
+            message: err.to_string(),
+        })?;
+        let mut validator = Validator { errors: Vec::new() };
+        let scene = file.build(&mut validator);
+        match validator.errors.is_empty() {
+            true => Ok(scene),
+            false => Err(SceneError::Invalid { source: source.to_string(), errors: validator.errors }),
+        }
+    }
+}
+
+// Formato del archivo. Los ángulos van en grados y las velocidades en radianes por segundo.
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    ship: ShipDesc,
+    star: StarDesc,
+    #[serde(default)]
+    planets: Vec<BodyDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ShipDesc {
+    model: String,
+    position: [f32; 3],
+    #[serde(default)]
+    yaw: f32,
+    #[serde(default = "default_ship_size")]
+    size: f32,
+    #[serde(default = "default_engine_nozzle")]
+    engine_nozzle: [f32; 3],
+    // Orientación del archivo: por defecto +Y arriba, +Z adelante y mano derecha
+    up_axis: Option<String>,
+    forward_axis: Option<String>,
+    handedness: Option<String>,
+    // `bounds_center` (por defecto) o `file_origin`
+    pivot: Option<String>,
+    // `from_file` (por defecto), `flat` o `smooth`, y ángulo de pliegue en grados
+    normals: Option<String>,
+    crease_angle: Option<f32>,
+}
+
+fn default_ship_size() -> f32 {
+    1.7
+}
+
+fn default_engine_nozzle() -> [f32; 3] {
+    [0.0, 0.0, -8.0]
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StarDesc {
+    name: String,
+    radius: f32,
+    #[serde(default)]
+    rotation_speed: f32,
+    // "sun" o "red_dwarf"; la temperatura y la luminosidad lo reemplazan
+    preset: Option<String>,
+    temperature: Option<f32>,
+    luminosity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BodyDesc {
+    name: String,
+    // earth_like, gas_giant, ice_planet, volcanic, desert, moon o procedural
+    shader: String,
+    radius: f32,
+    #[serde(default)]
+    rotation_speed: f32,
+    orbit: OrbitDesc,
+    procedural: Option<ProceduralDesc>,
+    atmosphere: Option<AtmosphereDesc>,
+    clouds: Option<CloudsDesc>,
+    rings: Option<RingsDesc>,
+    #[serde(default)]
+    moons: Vec<BodyDesc>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitDesc {
+    // Distancia al centro del cuerpo alrededor del que gira
+    radius: f32,
+    speed: f32,
+    // Ángulo inicial sobre la órbita
+    #[serde(default)]
+    phase: f32,
+    // Color de la línea de la órbita; sin color no se dibuja
+    color: Option<[u8; 3]>,
+    #[serde(default = "default_orbit_segments")]
+    segments: u32,
+}
+
+fn default_orbit_segments() -> u32 {
+    120
+}
+
+// Semilla del planeta procedural; los demás campos reemplazan lo que sale de ella
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProceduralDesc {
+    seed: u32,
+    sea_level: Option<f32>,
+    roughness: Option<f32>,
+    continent_scale: Option<f32>,
+    polar_cap: Option<f32>,
+    cloud_coverage: Option<f32>,
+    atmosphere_density: Option<f32>,
+}
+
+// Un preset ("earth_like", "hazy") con campos que lo reemplazan, o todos los campos
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AtmosphereDesc {
+    preset: Option<String>,
+    color: Option<[f32; 3]>,
+    density: Option<f32>,
+    thickness: Option<f32>,
+    mie: Option<f32>,
+}
+
+// Un preset ("earth_like", "haze") con campos que lo reemplazan, o todos los campos
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CloudsDesc {
+    preset: Option<String>,
+    altitude: Option<f32>,
+    rotation_speed: Option<f32>,
+    coverage: Option<f32>,
+    color: Option<[f32; 3]>,
+    opacity: Option<f32>,
+    scale: Option<f32>,
+    shadow_strength: Option<f32>,
+}
+
+// Un preset ("saturn_like") o una lista de bandas, con la inclinación del plano
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RingsDesc {
+    preset: Option<String>,
+    bands: Option<Vec<BandDesc>>,
+    #[serde(default)]
+    inclination: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BandDesc {
+    // Radios en radios del planeta
+    start: f32,
+    end: f32,
+    density: f32,
+    color: [f32; 3],
+}
+
+// Junta los errores de validación para informarlos todos de una vez
+struct Validator {
+    errors: Vec<String>,
+}
+
+impl Validator {
+    fn check(&mut self, ok: bool, context: &str, message: impl fmt::Display) {
+        if !ok {
+            self.errors.push(format!("{}: {}", context, message));
+        }
+    }
+
+    fn positive(&mut self, value: f32, context: &str, field: &str) {
+        self.check(value.is_finite() && value > 0.0, context, format_args!("`{}` debe ser mayor que 0 (es {})", field, value));
+    }
+
+    fn finite(&mut self, value: f32, context: &str, field: &str) {
+        self.check(value.is_finite(), context, format_args!("`{}` debe ser un número finito", field));
+    }
+
+    fn in_range(&mut self, value: f32, min: f32, max: f32, context: &str, field: &str) {
+        self.check((min..=max).contains(&value), context, format_args!("`{}` debe estar entre {} y {} (es {})", field, min, max, value));
+    }
+
+    fn color(&mut self, color: [f32; 3], context: &str, field: &str) {
+        self.check(color.iter().all(|c| (0.0..=1.0).contains(c)), context, format_args!("`{}` debe tener componentes entre 0 y 1", field));
+    }
+
+    // Valor de un campo que admite una lista de nombres; si falta se usa `default`
+    fn choice<T: Copy>(&mut self, value: Option<&str>, default: T, options: &[(&str, T)], context: &str, field: &str) -> T {
+        let Some(name) = value else {
+            return default;
+        };
+        match options.iter().find(|(option, _)| *option == name) {
+            Some(&(_, value)) => value,
+            None => {
+                let known: Vec<&str> = options.iter().map(|(option, _)| *option).collect();
+                self.unknown(context, field, name, &known);
+                default
+            }
+        }
+    }
+
+    fn unknown(&mut self, context: &str, what: &str, name: &str, known: &[&str]) {
+        self.errors.push(format!("{}: {} desconocido '{}' (se admite: {})", context, what, name, known.join(", ")));
+    }
+
+    // Toma el campo o, si falta, el del preset; si tampoco hay preset, es un error
+    fn field<T: Copy>(&mut self, value: Option<T>, preset: Option<T>, context: &str, field: &str) -> Option<T> {
+        let value = value.or(preset);
+        self.check(value.is_some(), context, format_args!("falta `{}` (o un `preset` que lo defina)", field));
+        value
+    }
+}
+
+impl SceneFile {
+    fn build(self, validator: &mut Validator) -> Scene {
+        let ship = self.ship.build(validator);
+        let sun = self.star.build(validator);
+
+        let mut names = vec![self.star.name.clone()];
+        let mut planets = Vec::new();
+        for planet in &self.planets {
+            let context = format!("planeta '{}'", planet.name);
+            validator.check(planet.orbit.radius > sun.scale + planet.radius, &context, format_args!(
+                "la órbita (radio {}) debe quedar fuera de la estrella (radio {} + {} del planeta)",
+                planet.orbit.radius, sun.scale, planet.radius
+            ));
+            planets.push(planet.build(validator, &context, &mut names, true));
+        }
+
+        let mut seen = Vec::new();
+        for name in names {
+            validator.check(!name.trim().is_empty(), "escena", "todos los cuerpos necesitan un nombre");
+            validator.check(!seen.contains(&name), "escena", format_args!("el nombre '{}' está repetido", name));
+            seen.push(name);
+        }
+
+        Scene { system: SolarSystem { sun, planets }, ship }
+    }
+}
+
+impl ShipDesc {
+    fn build(&self, validator: &mut Validator) -> ShipSpawn {
+        let context = "nave";
+        validator.check(!self.model.trim().is_empty(), context, "falta la ruta del modelo");
+        for (value, field) in self.position.iter().zip(["position.x", "position.y", "position.z"]) {
+            validator.finite(*value, context, field);
+        }
+        for (value, field) in self.engine_nozzle.iter().zip(["engine_nozzle.x", "engine_nozzle.y", "engine_nozzle.z"]) {
+            validator.finite(*value, context, field);
+        }
+        validator.finite(self.yaw, context, "yaw");
+        validator.positive(self.size, context, "size");
+
+        let axes = [
+            ("+x", Axis::PosX), ("-x", Axis::NegX),
+            ("+y", Axis::PosY), ("-y", Axis::NegY),
+            ("+z", Axis::PosZ), ("-z", Axis::NegZ),
+        ];
+        let errors = validator.errors.len();
+        let up_axis = validator.choice(self.up_axis.as_deref(), Axis::PosY, &axes, context, "up_axis");
+        let forward_axis = validator.choice(self.forward_axis.as_deref(), Axis::PosZ, &axes, context, "forward_axis");
+        if validator.errors.len() == errors {
+            validator.check(up_axis.vector().dot(&forward_axis.vector()) == 0.0, context, "`up_axis` y `forward_axis` deben ser perpendiculares");
+        }
+        let handedness = validator.choice(
+            self.handedness.as_deref(), Handedness::Right, &[("right", Handedness::Right), ("left", Handedness::Left)], context, "handedness",
+        );
+        let pivot = validator.choice(
+            self.pivot.as_deref(), Pivot::BoundsCenter, &[("bounds_center", Pivot::BoundsCenter), ("file_origin", Pivot::FileOrigin)], context, "pivot",
+        );
+
+        let normals = validator.choice(
+            self.normals.as_deref(), NormalMode::FromFile,
+            &[("from_file", NormalMode::FromFile), ("flat", NormalMode::Flat), ("smooth", NormalMode::Smooth)], context, "normals",
+        );
+        let default_import = ImportOptions::default();
+        let crease_angle = self.crease_angle.map_or(default_import.crease_angle, f32::to_radians);
+        if let Some(degrees) = self.crease_angle {
+            validator.in_range(degrees, 0.0, 180.0, context, "crease_angle");
+        }
+
+        ShipSpawn {
+            model: self.model.clone(),
+            position: Vec3::from(self.position),
+            yaw: self.yaw.to_radians(),
+            import: ImportOptions {
+                normals,
+                crease_angle,
+                up_axis,
+                forward_axis,
+                handedness,
+                pivot,
+                target_size: Some(self.size),
+                ..default_import
+            },
+            engine_nozzle: Vec3::from(self.engine_nozzle),
+        }
+    }
+}
+
+impl StarDesc {
+    fn build(&self, validator: &mut Validator) -> CelestialBody {
+        let context = format!("estrella '{}'", self.name);
+        let preset = match self.preset.as_deref() {
+            None | Some("sun") => Star::sun(),
+            Some("red_dwarf") => Star::red_dwarf(),
+            Some(other) => {
+                validator.unknown(&context, "preset", other, &["sun", "red_dwarf"]);
+                Star::sun()
+            }
+        };
+        let star = Star::new(self.temperature.unwrap_or(preset.temperature), self.luminosity.unwrap_or(preset.luminosity));
+        validator.positive(self.radius, &context, "radius");
+        validator.finite(self.rotation_speed, &context, "rotation_speed");
+        validator.in_range(star.temperature, 1000.0, 40000.0, &context, "temperature");
+        validator.positive(star.luminosity, &context, "luminosity");
+        CelestialBody::star(&self.name, self.radius, self.rotation_speed, star)
+    }
+}
+
+impl BodyDesc {
+    // `is_planet`: las lunas sólo tienen superficie; sus capas y sus lunas no se dibujan
+    fn build(&self, validator: &mut Validator, context: &str, names: &mut Vec<String>, is_planet: bool) -> CelestialBody {
+        names.push(self.name.clone());
+        validator.positive(self.radius, context, "radius");
+        validator.finite(self.rotation_speed, context, "rotation_speed");
+        self.orbit.validate(validator, context);
+
+        let shader = match self.shader.as_str() {
+            "earth_like" => ShaderType::EarthLike,
+            "gas_giant" => ShaderType::GasGiant,
+            "ice_planet" => ShaderType::IcePlanet,
+            "volcanic" => ShaderType::Volcanic,
+            "desert" => ShaderType::Desert,
+            "moon" => ShaderType::Moon,
+            "procedural" => match &self.procedural {
+                Some(procedural) => ShaderType::Procedural(procedural.build(validator, context)),
+                None => {
+                    validator.check(false, context, "el shader `procedural` necesita una tabla `procedural` con `seed`");
+                    ShaderType::Moon
+                }
+            },
+            other => {
+                let known = ["earth_like", "gas_giant", "ice_planet", "volcanic", "desert", "moon", "procedural"];
+                validator.unknown(context, "shader", other, &known);
+                ShaderType::Moon
+            }
+        };
+        validator.check(self.procedural.is_none() || self.shader == "procedural", context, "la tabla `procedural` sólo se usa con `shader = \"procedural\"`");
+
+        let orbit = &self.orbit;
+        let mut body = match shader {
+            ShaderType::Procedural(params) => CelestialBody::procedural(&self.name, orbit.radius, self.radius, orbit.speed, self.rotation_speed, params),
+            shader => CelestialBody::new(&self.name, orbit.radius, self.radius, orbit.speed, self.rotation_speed, shader),
+        }
+        .with_orbit_phase(orbit.phase.to_radians());
+        if let Some(color) = orbit.color {
+            body = body.with_orbit_line(Color::new(color[0], color[1], color[2]), orbit.segments);
+        }
+
+        if !is_planet {
+            let layers = self.atmosphere.is_some() || self.clouds.is_some() || self.rings.is_some() || !self.moons.is_empty();
+            validator.check(!layers, context, "las lunas no admiten atmósfera, nubes, anillos ni lunas propias");
+            return body;
+        }
+
+        if let Some(atmosphere) = self.atmosphere.as_ref().and_then(|desc| desc.build(validator, context)) {
+            body = body.with_atmosphere(atmosphere);
+        }
+        if let Some(clouds) = self.clouds.as_ref().and_then(|desc| desc.build(validator, context)) {
+            body = body.with_clouds(clouds);
+        }
+        if let Some(rings) = &self.rings {
+            if let Some(system) = rings.build(validator, context) {
+                body = body.with_rings(system, rings.inclination.to_radians());
+            }
+        }
+        for moon in &self.moons {
+            let moon_context = format!("luna '{}' de '{}'", moon.name, self.name);
+            validator.check(moon.orbit.radius > self.radius + moon.radius, &moon_context, format_args!(
+                "la órbita (radio {}) debe quedar fuera del planeta (radio {} + {} de la luna)",
+                moon.orbit.radius, self.radius, moon.radius
+            ));
+            body = body.with_moon(moon.build(validator, &moon_context, names, false));
+        }
+        body
+    }
+}
+
+impl OrbitDesc {
+    fn validate(&self, validator: &mut Validator, context: &str) {
+        validator.positive(self.radius, context, "orbit.radius");
+        validator.finite(self.speed, context, "orbit.speed");
+        validator.finite(self.phase, context, "orbit.phase");
+        validator.check((8..=4096).contains(&self.segments), context, format_args!("`orbit.segments` debe estar entre 8 y 4096 (es {})", self.segments));
+    }
+}
+
+impl ProceduralDesc {
+    fn build(&self, validator: &mut Validator, context: &str) -> PlanetParams {
+        let mut params = PlanetParams::from_seed(self.seed);
+        let overrides = [
+            (self.sea_level, &mut params.sea_level, -1.0, 1.0, "procedural.sea_level"),
+            (self.roughness, &mut params.roughness, 0.0, 1.0, "procedural.roughness"),
+            (self.continent_scale, &mut params.continent_scale, 0.1, 20.0, "procedural.continent_scale"),
+            (self.polar_cap, &mut params.polar_cap, 0.0, 1.0, "procedural.polar_cap"),
+            (self.cloud_coverage, &mut params.cloud_coverage, 0.0, 1.0, "procedural.cloud_coverage"),
+            (self.atmosphere_density, &mut params.atmosphere_density, 0.0, 5.0, "procedural.atmosphere_density"),
+        ];
+        for (value, target, min, max, field) in overrides {
+            if let Some(value) = value {
+                validator.in_range(value, min, max, context, field);
+                *target = value;
+            }
+        }
+        params
+    }
+}
+
+impl AtmosphereDesc {
+    fn build(&self, validator: &mut Validator, context: &str) -> Option<Atmosphere> {
+        let preset = match self.preset.as_deref() {
+            None => None,
+            Some("earth_like") => Some(Atmosphere::earth_like()),
+            Some("hazy") => Some(Atmosphere::hazy()),
+            Some(other) => {
+                validator.unknown(context, "preset de atmósfera", other, &["earth_like", "hazy"]);
+                return None;
+            }
+        };
+        let atmosphere = Atmosphere {
+            color: Vec3::from(validator.field(self.color, preset.map(|p| p.color.into()), context, "atmosphere.color")?),
+            density: validator.field(self.density, preset.map(|p| p.density), context, "atmosphere.density")?,
+            thickness: validator.field(self.thickness, preset.map(|p| p.thickness), context, "atmosphere.thickness")?,
+            mie: validator.field(self.mie, preset.map(|p| p.mie), context, "atmosphere.mie")?,
+        };
+        validator.color(atmosphere.color.into(), context, "atmosphere.color");
+        validator.in_range(atmosphere.density, 0.0, 10.0, context, "atmosphere.density");
+        validator.in_range(atmosphere.thickness, 0.01, 1.0, context, "atmosphere.thickness");
+        validator.in_range(atmosphere.mie, 0.0, 1.0, context, "atmosphere.mie");
+        Some(atmosphere)
+    }
+}
+
+impl CloudsDesc {
+    fn build(&self, validator: &mut Validator, context: &str) -> Option<CloudLayer> {
+        let preset = match self.preset.as_deref() {
+            None => None,
+            Some("earth_like") => Some(CloudLayer::earth_like()),
+            Some("haze") => Some(CloudLayer::haze()),
+            Some(other) => {
+                validator.unknown(context, "preset de nubes", other, &["earth_like", "haze"]);
+                return None;
+            }
+        };
+        let clouds = CloudLayer {
+            altitude: validator.field(self.altitude, preset.map(|p| p.altitude), context, "clouds.altitude")?,
+            rotation_speed: validator.field(self.rotation_speed, preset.map(|p| p.rotation_speed), context, "clouds.rotation_speed")?,
+            coverage: validator.field(self.coverage, preset.map(|p| p.coverage), context, "clouds.coverage")?,
+            color: Vec3::from(validator.field(self.color, preset.map(|p| p.color.into()), context, "clouds.color")?),
+            opacity: validator.field(self.opacity, preset.map(|p| p.opacity), context, "clouds.opacity")?,
+            scale: validator.field(self.scale, preset.map(|p| p.scale), context, "clouds.scale")?,
+            shadow_strength: validator.field(self.shadow_strength, preset.map(|p| p.shadow_strength), context, "clouds.shadow_strength")?,
+        };
+        validator.in_range(clouds.altitude, 0.001, 1.0, context, "clouds.altitude");
+        validator.finite(clouds.rotation_speed, context, "clouds.rotation_speed");
+        validator.in_range(clouds.coverage, 0.0, 1.0, context, "clouds.coverage");
+        validator.color(clouds.color.into(), context, "clouds.color");
+        validator.in_range(clouds.opacity, 0.0, 1.0, context, "clouds.opacity");
+        validator.positive(clouds.scale, context, "clouds.scale");
+        validator.in_range(clouds.shadow_strength, 0.0, 1.0, context, "clouds.shadow_strength");
+        Some(clouds)
+    }
+}
+
+impl RingsDesc {
+    fn build(&self, validator: &mut Validator, context: &str) -> Option<RingSystem> {
+        validator.finite(self.inclination, context, "rings.inclination");
+        match (self.preset.as_deref(), &self.bands) {
+            (Some("saturn_like"), None) => Some(RingSystem::saturn_like()),
+            (Some(other), None) => {
+                validator.unknown(context, "preset de anillos", other, &["saturn_like"]);
+                None
+            }
+            (None, Some(bands)) => {
+                validator.check(!bands.is_empty(), context, "`rings.bands` no puede estar vacío");
+                for (i, band) in bands.iter().enumerate() {
+                    let field = format!("rings.bands[{}]", i);
+                    validator.finite(band.start, context, &format!("{}.start", field));
+                    validator.finite(band.end, context, &format!("{}.end", field));
+                    validator.finite(band.density, context, &format!("{}.density", field));
+                    validator.check(band.start >= 1.0 && band.start < band.end, context, format_args!(
+                        "`{}` debe empezar fuera del planeta (start >= 1) y terminar después de empezar (es {} a {})",
+                        field, band.start, band.end
+                    ));
+                    validator.check(!band.density.is_finite() || band.density >= 0.0, context, format_args!("`{}.density` no puede ser negativa", field));
+                    validator.color(band.color, context, &format!("{}.color", field));
+                }
+                let bands = bands.iter()
+                    .map(|band| RingBand { start: band.start, end: band.end, density: band.density, color: Vec3::from(band.color) })
+                    .collect();
+                Some(RingSystem::new(bands))
+            }
+            _ => {
+                validator.check(false, context, "los anillos necesitan un `preset` o una lista de `bands`, no ambos");
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = r#"
+[ship]
+model = "Jett.obj"
+position = [0.0, 8.0, 35.0]
+
+[star]
+name = "Sol"
+radius = 2.0
+"#;
+
+    // Errores de validación de la escena base con estos planetas agregados
+    fn errors(planets: &str) -> Vec<String> {
+        match Scene::parse(&format!("{}{}", BASE, planets), "prueba") {
+            Err(SceneError::Invalid { errors, .. }) => errors,
+            Err(err) => panic!("error inesperado: {}", err),
+            Ok(_) => panic!("la escena debía ser inválida"),
+        }
+    }
+
+    fn assert_error(errors: &[String], expected: &str) {
+        assert!(errors.iter().any(|error| error.contains(expected)), "no aparece '{}' en {:?}", expected, errors);
+    }
+
+    #[test]
+    fn builtin_scene_is_valid() {
+        let scene = Scene::parse(BUILTIN_SCENE, "incluida").unwrap_or_else(|err| panic!("{}", err));
+        assert_eq!(scene.system.planets.len(), 6);
+    }
+
+    #[test]
+    fn unknown_shader() {
+        let errors = errors(r#"
+[[planets]]
+name = "Lava"
+shader = "lava"
+radius = 0.5
+orbit = { radius = 8.0, speed = 0.5 }
+"#);
+        assert_error(&errors, "planeta 'Lava': shader desconocido 'lava'");
+    }
+
+    #[test]
+    fn duplicate_name() {
+        let errors = errors(r#"
+[[planets]]
+name = "Sol"
+shader = "moon"
+radius = 0.5
+orbit = { radius = 8.0, speed = 0.5 }
+"#);
+        assert_error(&errors, "el nombre 'Sol' está repetido");
+    }
+
+    #[test]
+    fn orbit_inside_star() {
+        let errors = errors(r#"
+[[planets]]
+name = "Rasante"
+shader = "moon"
+radius = 0.5
+orbit = { radius = 2.2, speed = 0.5 }
+"#);
+        assert_error(&errors, "planeta 'Rasante': la órbita (radio 2.2) debe quedar fuera de la estrella");
+    }
+
+    #[test]
+    fn rings_preset_and_bands() {
+        let errors = errors(r#"
+[[planets]]
+name = "Anillado"
+shader = "gas_giant"
+radius = 1.5
+orbit = { radius = 15.0, speed = 0.3 }
+rings = { preset = "saturn_like", bands = [{ start = 1.2, end = 1.8, density = 0.8, color = [0.8, 0.7, 0.6] }] }
+"#);
+        assert_error(&errors, "los anillos necesitan un `preset` o una lista de `bands`, no ambos");
+    }
+}
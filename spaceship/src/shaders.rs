@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec3, Vec4, Mat3};
+use nalgebra_glm::{Vec3, Vec4, Mat3, Mat4, mat4_to_mat3};
 use crate::vertex::Vertex;
 use crate::Uniforms;
 
@@ -26,21 +26,70 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     1.0
   );
 
-  let model_mat3 = Mat3::new(
-    uniforms.model_matrix[0], uniforms.model_matrix[1], uniforms.model_matrix[2],
-    uniforms.model_matrix[4], uniforms.model_matrix[5], uniforms.model_matrix[6],
-    uniforms.model_matrix[8], uniforms.model_matrix[9], uniforms.model_matrix[10]
-  );
-  let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
+  let world_position = uniforms.model_matrix * position;
+
+  let transformed_normal = uniforms.normal_matrix * vertex.normal;
 
-  let transformed_normal = normal_matrix * vertex.normal;
+  // La tangente sigue a la superficie, así que se transforma con la matriz de modelo
+  let tangent = mat4_to_mat3(&uniforms.model_matrix) * vertex.tangent.xyz();
 
   Vertex {
     position: vertex.position,
     normal: vertex.normal,
     tex_coords: vertex.tex_coords,
     color: vertex.color,
+    tangent: vertex.tangent,
     transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
     transformed_normal,
+    transformed_tangent: Vec4::new(tangent.x, tangent.y, tangent.z, vertex.tangent.w),
+    world_position: world_position.xyz(),
+  }
+}
+
+// Inversa transpuesta de la parte 3x3 del modelo, para transformar normales
+pub fn normal_matrix(model_matrix: &Mat4) -> Mat3 {
+  mat4_to_mat3(model_matrix).try_inverse().unwrap_or(Mat3::identity()).transpose()
+}
+
+// Recorta el triángulo contra el plano cercano (z >= -w en espacio de recorte). `clip` son
+// las posiciones de los vértices antes de dividir por w. Devuelve ninguno, uno o dos
+// triángulos, con los atributos de los vértices nuevos interpolados sobre las aristas.
+pub fn clip_near(vertices: [&Vertex; 3], clip: [Vec4; 3], uniforms: &Uniforms) -> Vec<[Vertex; 3]> {
+  let distance = clip.map(|position| position.z + position.w);
+  if distance.iter().all(|&d| d >= 0.0) {
+    return vec![vertices.map(Vertex::clone)];
+  }
+
+  let mut polygon = Vec::with_capacity(4);
+  for i in 0..3 {
+    let j = (i + 1) % 3;
+    if distance[i] >= 0.0 {
+      polygon.push(vertices[i].clone());
+    }
+    if (distance[i] >= 0.0) != (distance[j] >= 0.0) {
+      let t = distance[i] / (distance[i] - distance[j]);
+      let position = clip[i] + (clip[j] - clip[i]) * t;
+      polygon.push(lerp_vertex(vertices[i], vertices[j], t, &position, uniforms));
+    }
+  }
+
+  (1..polygon.len().saturating_sub(1))
+    .map(|i| [polygon[0].clone(), polygon[i].clone(), polygon[i + 1].clone()])
+    .collect()
+}
+
+// Vértice en la fracción `t` de la arista a-b, con su posición de pantalla a partir de la de recorte
+fn lerp_vertex(a: &Vertex, b: &Vertex, t: f32, clip: &Vec4, uniforms: &Uniforms) -> Vertex {
+  let screen_position = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+  Vertex {
+    position: a.position + (b.position - a.position) * t,
+    normal: a.normal + (b.normal - a.normal) * t,
+    tex_coords: a.tex_coords + (b.tex_coords - a.tex_coords) * t,
+    color: a.color,
+    tangent: a.tangent + (b.tangent - a.tangent) * t,
+    transformed_position: screen_position.xyz(),
+    transformed_normal: a.transformed_normal + (b.transformed_normal - a.transformed_normal) * t,
+    transformed_tangent: a.transformed_tangent + (b.transformed_tangent - a.transformed_tangent) * t,
+    world_position: a.world_position + (b.world_position - a.world_position) * t,
   }
 }
@@ -12,11 +12,11 @@ struct Star {
 }
 
 impl Skybox {
-    pub fn new(star_count: usize) -> Self {
+    pub fn new(star_count: usize, seed: u32) -> Self {
         let mut stars = Vec::new();
         
-        // Generar estrellas proceduralmente usando un seed simple
-        let mut seed = 12345u32;
+        // Generar estrellas proceduralmente a partir de la semilla
+        let mut seed = seed;
         
         for _ in 0..star_count {
             // Generador simple de números pseudo-aleatorios
@@ -0,0 +1,188 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use crate::fragment::Fragment;
+use crate::color::Color;
+use crate::noise::fbm;
+use crate::Uniforms;
+
+// Parámetros de una estrella; su aspecto y su luz se derivan de la temperatura efectiva
+#[derive(Clone, Copy)]
+pub struct Star {
+    // Temperatura efectiva de la fotosfera en kelvin
+    pub temperature: f32,
+    // Luminosidad en luminosidades solares
+    pub luminosity: f32,
+}
+
+// Tamaño del billboard de la corona relativo al radio de la estrella
+const CORONA_EXTENT: f32 = 4.0;
+// Protuberancias simultáneas como máximo y duración de cada una (segundos)
+const PROMINENCES: u32 = 3;
+const PROMINENCE_LIFETIME: f32 = 9.0;
+// Temperatura del sol: su luz se toma como blanca (balance de blancos de la cámara)
+const SOLAR_TEMPERATURE: f32 = 5778.0;
+// Distancia a la que una estrella de luminosidad 1 ilumina con intensidad 1 (órbita terrestre)
+const REFERENCE_DISTANCE: f32 = 8.0;
+
+impl Star {
+    pub fn new(temperature: f32, luminosity: f32) -> Self {
+        Star { temperature, luminosity }
+    }
+
+    pub fn sun() -> Self {
+        Star::new(SOLAR_TEMPERATURE, 1.0)
+    }
+
+    // Enana roja: luz anaranjada y más débil. La luminosidad es mucho mayor que la de una
+    // enana roja real para que los planetas del sistema sigan siendo visibles.
+    pub fn red_dwarf() -> Self {
+        Star::new(3000.0, 0.4)
+    }
+
+    // Color de la luz emitida, relativo al del sol
+    pub fn light_color(&self) -> Vec3 {
+        blackbody(self.temperature).component_div(&blackbody(SOLAR_TEMPERATURE))
+    }
+
+    // Luz (color por intensidad) que recibe un punto a `distance` de la estrella. El flujo cae
+    // con el cuadrado de la distancia y luego se comprime como una exposición de cámara,
+    // para que los planetas lejanos queden más oscuros pero no negros.
+    pub fn light_at(&self, distance: f32) -> Vec3 {
+        let flux = self.luminosity * (REFERENCE_DISTANCE / distance.max(1e-3)).powi(2);
+        let exposure = 4.0;
+        let intensity = (1.0 - (-flux * exposure).exp()) / (1.0 - (-exposure).exp());
+        self.light_color() * intensity
+    }
+
+    // Coeficiente del oscurecimiento lineal del limbo: más fuerte en estrellas frías
+    fn limb_darkening(&self) -> f32 {
+        (1.2 - self.temperature / 9000.0).clamp(0.25, 0.9)
+    }
+
+    pub fn corona_scale(&self) -> f32 {
+        CORONA_EXTENT
+    }
+}
+
+// Aproximación del color de un cuerpo negro (ajuste de Tanner Helland), canales en [0, 1]
+pub fn blackbody(temperature: f32) -> Vec3 {
+    let t = temperature.clamp(1000.0, 40000.0) / 100.0;
+
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.698_73 * (t - 60.0).powf(-0.133_204_76)
+    };
+    let g = if t <= 66.0 {
+        99.470_8 * t.ln() - 161.119_57
+    } else {
+        288.122_17 * (t - 60.0).powf(-0.075_514_85)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.517_73 * (t - 10.0).ln() - 305.044_8
+    };
+
+    Vec3::new(r, g, b).map(|c| (c / 255.0).clamp(0.0, 1.0))
+}
+
+// Fotosfera: granulación con ruido fBm animado y oscurecimiento del limbo
+pub fn star_surface_shader(fragment: &Fragment, uniforms: &Uniforms, star: &Star, vertex_position: &Vec3, vertex_normal: &Vec3) -> Color {
+    let time = uniforms.time as f32 * 0.016;
+    let direction = vertex_position.normalize();
+
+    // Gránulos pequeños sobre supergránulos mayores, ambos cambiando lentamente
+    let drift = Vec3::new(time * 0.05, time * 0.03, -time * 0.04);
+    let granules = fbm(&(direction * 24.0 + drift), 3);
+    let supergranules = fbm(&(direction * 5.0 - drift * 0.5), 2);
+    let convection = (granules * 2.0 + supergranules).clamp(-1.0, 1.0);
+
+    // mu = coseno entre la normal y la dirección de vista: 1 en el centro del disco, 0 en el borde
+    let to_camera = uniforms.camera_position - fragment.world_position;
+    let mu = if to_camera.magnitude() > 1e-4 {
+        vertex_normal.normalize().dot(&to_camera.normalize()).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let limb = 1.0 - star.limb_darkening() * (1.0 - mu);
+
+    // Hacia el borde se ven capas más altas y frías, y el centro de los gránulos está más caliente
+    let temperature = star.temperature * (0.85 + 0.15 * mu) * (1.0 + convection * 0.05);
+    // Exposición algo alta: el centro del disco satura hacia blanco como en una foto
+    let color = blackbody(temperature) * limb * (1.0 + convection * 0.12) * 1.3;
+
+    Color::from_float(color.x, color.y, color.z)
+}
+
+// Corona sobre un billboard orientado a la cámara (coordenadas locales en [-1, 1]), con
+// serpentinas radiales y protuberancias en arco que aparecen y se apagan con el tiempo
+pub fn corona_shader(uniforms: &Uniforms, star: &Star, vertex_position: &Vec3) -> (Color, f32) {
+    let time = uniforms.time as f32 * 0.016;
+    let point = Vec2::new(vertex_position.x, vertex_position.y);
+    let distance = point.magnitude();
+    let star_radius = 1.0 / CORONA_EXTENT;
+    if distance < star_radius * 0.95 || distance > 1.0 {
+        return (Color::black(), 0.0);
+    }
+
+    let angle = point.y.atan2(point.x);
+    let around = Vec3::new(angle.cos(), angle.sin(), 0.0);
+
+    // Serpentinas: el brillo varía con el ángulo y se estira en dirección radial
+    let streamers = fbm(&(around * 3.0 + Vec3::new(0.0, 0.0, time * 0.02 + distance * 0.8)), 4);
+    let streamers = (0.6 + streamers * 2.5).max(0.1);
+
+    // Caída del brillo con la distancia al borde de la estrella, hasta anularse en el borde del billboard
+    let height = (distance - star_radius) / (1.0 - star_radius);
+    let falloff = (star_radius / distance).powi(3) * (1.0 - height).max(0.0);
+    let glow = falloff * streamers * 0.9 + (-height * 25.0).exp() * 0.5;
+
+    let corona_color = blackbody(star.temperature * 1.15);
+    let mut color = corona_color * glow;
+
+    // Protuberancias: gas en arco de emisión H-alfa, anclado en el limbo
+    let prominence_color = Vec3::new(1.0, 0.35, 0.3);
+    for slot in 0..PROMINENCES {
+        let local_time = time + slot as f32 * PROMINENCE_LIFETIME / PROMINENCES as f32;
+        let epoch = (local_time / PROMINENCE_LIFETIME).floor();
+        let phase = local_time / PROMINENCE_LIFETIME - epoch;
+        let seed = epoch as u32 * PROMINENCES + slot;
+        // Sólo algunas ventanas de tiempo tienen protuberancia
+        if random(seed * 3) > 0.7 {
+            continue;
+        }
+
+        let base_angle = random(seed * 3 + 1) * 2.0 * PI;
+        let normal = Vec2::new(base_angle.cos(), base_angle.sin());
+        let tangent = Vec2::new(-normal.y, normal.x);
+        let anchor = normal * star_radius;
+
+        let width = star_radius * (0.15 + random(seed * 3 + 2) * 0.15);
+        let rise = (phase * PI).sin();
+        let arc_height = star_radius * (0.1 + 0.35 * rise);
+
+        let offset = point - anchor;
+        let u = offset.dot(&tangent) / width;
+        let v = offset.dot(&normal) / arc_height;
+        if v < 0.0 {
+            continue;
+        }
+        let ellipse = (u * u + v * v).sqrt();
+        let thickness = 0.12;
+        let arc = (-((ellipse - 1.0) / thickness).powi(2)).exp();
+        // Filamentos a lo largo del arco
+        let filaments = 0.7 + 0.3 * (v * 12.0 + time * 2.0).sin();
+
+        color += prominence_color * (arc * filaments * rise * 1.5);
+    }
+
+    (Color::from_float(color.x, color.y, color.z), 1.0)
+}
+
+// Valor pseudoaleatorio en [0, 1) a partir de un entero
+fn random(seed: u32) -> f32 {
+    ((seed as f32 * 12.9898 + 78.233).sin() * 43_758.547).fract().abs()
+}
@@ -0,0 +1,453 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::color::Color;
+
+// Cómo se tratan las coordenadas UV fuera del rango [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WrapMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+    // Bilineal en los dos niveles de mipmap más cercanos y mezcla entre ellos
+    Trilinear,
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(std::io::Error),
+    UnsupportedFormat(String),
+    Decode(String),
+    Encode(String),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::Io(err) => write!(f, "error de lectura: {}", err),
+            TextureError::UnsupportedFormat(ext) => write!(f, "formato de textura no soportado: '{}'", ext),
+            TextureError::Decode(msg) => write!(f, "error decodificando textura: {}", msg),
+            TextureError::Encode(msg) => write!(f, "error codificando textura: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for TextureError {
+    fn from(err: std::io::Error) -> Self {
+        TextureError::Io(err)
+    }
+}
+
+struct MipLevel {
+    width: usize,
+    height: usize,
+    texels: Vec<Vec3>,
+}
+
+pub struct Texture {
+    levels: Vec<MipLevel>,
+    pub wrap: WrapMode,
+    pub filter: FilterMode,
+}
+
+impl Texture {
+    // Crea una textura a partir de texels RGB en [0, 1], fila por fila desde arriba
+    pub fn from_rgb(width: usize, height: usize, texels: Vec<Vec3>) -> Self {
+        assert_eq!(texels.len(), width * height, "tamaño de textura inconsistente");
+        let mut texture = Texture {
+            levels: vec![MipLevel { width, height, texels }],
+            wrap: WrapMode::Repeat,
+            filter: FilterMode::Trilinear,
+        };
+        texture.generate_mipmaps();
+        texture
+    }
+
+    // Carga PNG, PPM (P3/P6) o TGA según la extensión del archivo
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TextureError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .unwrap_or_default();
+
+        let (width, height, texels) = match extension.as_str() {
+            "png" => decode_png(path)?,
+            "ppm" => decode_ppm(&fs::read(path)?)?,
+            "tga" => decode_tga(&fs::read(path)?)?,
+            _ => return Err(TextureError::UnsupportedFormat(extension)),
+        };
+
+        if width == 0 || height == 0 {
+            return Err(TextureError::Decode("imagen vacía".to_string()));
+        }
+
+        Ok(Texture::from_rgb(width, height, texels))
+    }
+
+    pub fn with_wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    // Texels del nivel de mayor detalle, fila por fila desde arriba
+    pub fn texels(&self) -> &[Vec3] {
+        &self.levels[0].texels
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), TextureError> {
+        write_png(path, self.width(), self.height(), self.texels())
+    }
+
+    // Reconstruye la cadena de mipmaps promediando bloques de 2x2 hasta llegar a 1x1
+    pub fn generate_mipmaps(&mut self) {
+        self.levels.truncate(1);
+
+        while let Some(previous) = self.levels.last() {
+            if previous.width == 1 && previous.height == 1 {
+                break;
+            }
+
+            let width = (previous.width / 2).max(1);
+            let height = (previous.height / 2).max(1);
+            let mut texels = Vec::with_capacity(width * height);
+
+            for y in 0..height {
+                for x in 0..width {
+                    let x0 = (x * 2).min(previous.width - 1);
+                    let x1 = (x * 2 + 1).min(previous.width - 1);
+                    let y0 = (y * 2).min(previous.height - 1);
+                    let y1 = (y * 2 + 1).min(previous.height - 1);
+
+                    let sum = previous.texels[y0 * previous.width + x0]
+                        + previous.texels[y0 * previous.width + x1]
+                        + previous.texels[y1 * previous.width + x0]
+                        + previous.texels[y1 * previous.width + x1];
+                    texels.push(sum * 0.25);
+                }
+            }
+
+            self.levels.push(MipLevel { width, height, texels });
+        }
+    }
+
+    // Nivel de detalle a partir del área UV que cubre un píxel de pantalla
+    pub fn lod(&self, uv_footprint: f32) -> f32 {
+        let texels_per_pixel = uv_footprint * (self.width() * self.height()) as f32;
+        if texels_per_pixel <= 1.0 {
+            0.0
+        } else {
+            (0.5 * texels_per_pixel.log2()).min((self.levels.len() - 1) as f32)
+        }
+    }
+
+    pub fn sample(&self, uv: Vec2, uv_footprint: f32) -> Vec3 {
+        match self.filter {
+            FilterMode::Nearest => self.sample_nearest(0, uv),
+            FilterMode::Bilinear => self.sample_bilinear(0, uv),
+            FilterMode::Trilinear => {
+                let lod = self.lod(uv_footprint);
+                let level = lod.floor() as usize;
+                let blend = lod - level as f32;
+                let near = self.sample_bilinear(level, uv);
+                if blend <= 0.0 || level + 1 >= self.levels.len() {
+                    near
+                } else {
+                    near * (1.0 - blend) + self.sample_bilinear(level + 1, uv) * blend
+                }
+            }
+        }
+    }
+
+    pub fn sample_color(&self, uv: Vec2, uv_footprint: f32) -> Color {
+        let texel = self.sample(uv, uv_footprint);
+        Color::from_float(texel.x, texel.y, texel.z)
+    }
+
+    fn sample_nearest(&self, level: usize, uv: Vec2) -> Vec3 {
+        let mip = &self.levels[level];
+        let x = self.wrap_coord((uv.x * mip.width as f32).floor() as i64, mip.width);
+        let y = self.wrap_coord((uv.y * mip.height as f32).floor() as i64, mip.height);
+        mip.texels[y * mip.width + x]
+    }
+
+    fn sample_bilinear(&self, level: usize, uv: Vec2) -> Vec3 {
+        let mip = &self.levels[level];
+        let u = uv.x * mip.width as f32 - 0.5;
+        let v = uv.y * mip.height as f32 - 0.5;
+        let x0 = u.floor();
+        let y0 = v.floor();
+        let tx = u - x0;
+        let ty = v - y0;
+
+        let x0 = x0 as i64;
+        let y0 = y0 as i64;
+        let xa = self.wrap_coord(x0, mip.width);
+        let xb = self.wrap_coord(x0 + 1, mip.width);
+        let ya = self.wrap_coord(y0, mip.height);
+        let yb = self.wrap_coord(y0 + 1, mip.height);
+
+        let top = mip.texels[ya * mip.width + xa] * (1.0 - tx) + mip.texels[ya * mip.width + xb] * tx;
+        let bottom = mip.texels[yb * mip.width + xa] * (1.0 - tx) + mip.texels[yb * mip.width + xb] * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+
+    fn wrap_coord(&self, coord: i64, size: usize) -> usize {
+        let size = size as i64;
+        let wrapped = match self.wrap {
+            WrapMode::Repeat => coord.rem_euclid(size),
+            WrapMode::MirroredRepeat => {
+                let period = coord.rem_euclid(size * 2);
+                if period < size { period } else { size * 2 - 1 - period }
+            }
+            WrapMode::ClampToEdge => coord.clamp(0, size - 1),
+        };
+        wrapped as usize
+    }
+}
+
+// Guarda texels RGB en [0, 1] (los de fuera del rango se recortan) como PNG de 8 bits
+pub fn write_png<P: AsRef<Path>>(path: P, width: usize, height: usize, texels: &[Vec3]) -> Result<(), TextureError> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|err| TextureError::Encode(err.to_string()))?;
+    let data: Vec<u8> = texels.iter()
+        .flat_map(|texel| [texel.x, texel.y, texel.z])
+        .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect();
+    writer.write_image_data(&data).map_err(|err| TextureError::Encode(err.to_string()))?;
+    writer.finish().map_err(|err| TextureError::Encode(err.to_string()))
+}
+
+type DecodedImage = (usize, usize, Vec<Vec3>);
+
+// Píxeles como máximo de una imagen (8192 x 8192); un encabezado dañado no debe reservar
+// más memoria de la que tiene la máquina
+const MAX_PIXELS: usize = 8192 * 8192;
+
+// Cantidad de píxeles de una imagen de `width` x `height`, o error si pasa del límite
+fn pixel_count(format: &str, width: usize, height: usize) -> Result<usize, TextureError> {
+    width.checked_mul(height)
+        .filter(|&count| count <= MAX_PIXELS)
+        .ok_or_else(|| TextureError::Decode(format!("{}: tamaño {} x {} demasiado grande", format, width, height)))
+}
+
+fn decode_png(path: &Path) -> Result<DecodedImage, TextureError> {
+    let mut decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().map_err(|err| TextureError::Decode(err.to_string()))?;
+
+    let buffer_size = reader
+        .output_buffer_size()
+        .ok_or_else(|| TextureError::Decode("PNG demasiado grande".to_string()))?;
+    let mut buffer = vec![0; buffer_size];
+    let info = reader.next_frame(&mut buffer).map_err(|err| TextureError::Decode(err.to_string()))?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Rgb => 3,
+        png::ColorType::Rgba => 4,
+        png::ColorType::Indexed => {
+            return Err(TextureError::Decode("PNG indexado sin expandir".to_string()));
+        }
+    };
+
+    let width = info.width as usize;
+    let height = info.height as usize;
+    let mut texels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row = &buffer[y * info.line_size..];
+        for x in 0..width {
+            let pixel = &row[x * channels..];
+            let texel = if channels < 3 {
+                let gray = pixel[0] as f32 / 255.0;
+                Vec3::new(gray, gray, gray)
+            } else {
+                Vec3::new(pixel[0] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[2] as f32 / 255.0)
+            };
+            texels.push(texel);
+        }
+    }
+
+    Ok((width, height, texels))
+}
+
+fn decode_ppm(data: &[u8]) -> Result<DecodedImage, TextureError> {
+    let mut cursor = 0;
+    let mut next_token = |data: &[u8]| -> Option<String> {
+        // Saltar espacios y comentarios (# hasta fin de línea)
+        loop {
+            while cursor < data.len() && data[cursor].is_ascii_whitespace() {
+                cursor += 1;
+            }
+            if cursor < data.len() && data[cursor] == b'#' {
+                while cursor < data.len() && data[cursor] != b'\n' {
+                    cursor += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        let start = cursor;
+        while cursor < data.len() && !data[cursor].is_ascii_whitespace() {
+            cursor += 1;
+        }
+        (cursor > start).then(|| String::from_utf8_lossy(&data[start..cursor]).into_owned())
+    };
+
+    let invalid = |what: &str| TextureError::Decode(format!("PPM: {} inválido", what));
+    let magic = next_token(data).ok_or_else(|| invalid("encabezado"))?;
+    let width: usize = next_token(data).and_then(|t| t.parse().ok()).ok_or_else(|| invalid("ancho"))?;
+    let height: usize = next_token(data).and_then(|t| t.parse().ok()).ok_or_else(|| invalid("alto"))?;
+    let max_value: u32 = next_token(data).and_then(|t| t.parse().ok()).ok_or_else(|| invalid("valor máximo"))?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("valor máximo"));
+    }
+    let scale = 1.0 / max_value as f32;
+    let sample_count = pixel_count("PPM", width, height)? * 3;
+    let incomplete = || TextureError::Decode("PPM: datos incompletos".to_string());
+
+    let samples: Vec<u32> = match magic.as_str() {
+        "P3" => {
+            // Cada muestra ocupa al menos un carácter del archivo
+            if sample_count > data.len() {
+                return Err(incomplete());
+            }
+            let mut samples = Vec::with_capacity(sample_count);
+            for _ in 0..sample_count {
+                let value = next_token(data).and_then(|t| t.parse().ok()).ok_or_else(|| invalid("pixel"))?;
+                samples.push(value);
+            }
+            samples
+        }
+        "P6" => {
+            // Un único espacio separa el encabezado de los datos binarios
+            let start = cursor + 1;
+            let bytes_per_sample = if max_value < 256 { 1 } else { 2 };
+            let end = start + sample_count * bytes_per_sample;
+            data.get(start..end).ok_or_else(incomplete)?
+                .chunks(bytes_per_sample)
+                .map(|bytes| if bytes_per_sample == 1 {
+                    bytes[0] as u32
+                } else {
+                    ((bytes[0] as u32) << 8) | bytes[1] as u32
+                })
+                .collect()
+        }
+        _ => return Err(TextureError::UnsupportedFormat(format!("PPM {}", magic))),
+    };
+
+    let texels = samples
+        .chunks(3)
+        .map(|rgb| Vec3::new(rgb[0] as f32 * scale, rgb[1] as f32 * scale, rgb[2] as f32 * scale))
+        .collect();
+
+    Ok((width, height, texels))
+}
+
+fn decode_tga(data: &[u8]) -> Result<DecodedImage, TextureError> {
+    if data.len() < 18 {
+        return Err(TextureError::Decode("TGA: encabezado incompleto".to_string()));
+    }
+
+    let id_length = data[0] as usize;
+    let color_map_type = data[1];
+    let image_type = data[2];
+    let width = u16::from_le_bytes([data[12], data[13]]) as usize;
+    let height = u16::from_le_bytes([data[14], data[15]]) as usize;
+    let bits_per_pixel = data[16];
+    let descriptor = data[17];
+
+    if color_map_type != 0 {
+        return Err(TextureError::UnsupportedFormat("TGA con paleta".to_string()));
+    }
+
+    let (grayscale, compressed) = match image_type {
+        2 => (false, false),
+        3 => (true, false),
+        10 => (false, true),
+        11 => (true, true),
+        _ => return Err(TextureError::UnsupportedFormat(format!("TGA tipo {}", image_type))),
+    };
+
+    let bytes_per_pixel = match (grayscale, bits_per_pixel) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => return Err(TextureError::UnsupportedFormat(format!("TGA de {} bits", bits_per_pixel))),
+    };
+
+    let pixel_count = pixel_count("TGA", width, height)?;
+    let mut cursor = 18 + id_length;
+    let truncated = || TextureError::Decode("TGA: datos incompletos".to_string());
+    // Sin compresión cada píxel ocupa `bytes_per_pixel`; con RLE, un paquete de un byte de
+    // cabecera y un píxel da como mucho 128
+    let remaining = data.len().saturating_sub(cursor);
+    let max_pixels = if compressed { remaining / (1 + bytes_per_pixel) * 128 } else { remaining / bytes_per_pixel };
+    if pixel_count > max_pixels {
+        return Err(truncated());
+    }
+    let mut pixels: Vec<&[u8]> = Vec::with_capacity(pixel_count);
+
+    if compressed {
+        while pixels.len() < pixel_count {
+            let header = *data.get(cursor).ok_or_else(truncated)?;
+            cursor += 1;
+            let count = (header & 0x7F) as usize + 1;
+            if header & 0x80 != 0 {
+                let pixel = data.get(cursor..cursor + bytes_per_pixel).ok_or_else(truncated)?;
+                cursor += bytes_per_pixel;
+                pixels.extend(std::iter::repeat_n(pixel, count));
+            } else {
+                for _ in 0..count {
+                    pixels.push(data.get(cursor..cursor + bytes_per_pixel).ok_or_else(truncated)?);
+                    cursor += bytes_per_pixel;
+                }
+            }
+        }
+        pixels.truncate(pixel_count);
+    } else {
+        let end = cursor + pixel_count * bytes_per_pixel;
+        pixels.extend(data.get(cursor..end).ok_or_else(truncated)?.chunks(bytes_per_pixel));
+    }
+
+    // El bit 5 del descriptor indica origen arriba; por defecto TGA empieza abajo
+    let top_down = descriptor & 0x20 != 0;
+    let mut texels = Vec::with_capacity(pixel_count);
+    for y in 0..height {
+        let source_row = if top_down { y } else { height - 1 - y };
+        for x in 0..width {
+            let pixel = pixels[source_row * width + x];
+            let texel = if grayscale {
+                let gray = pixel[0] as f32 / 255.0;
+                Vec3::new(gray, gray, gray)
+            } else {
+                // TGA guarda los canales en orden BGR(A)
+                Vec3::new(pixel[2] as f32 / 255.0, pixel[1] as f32 / 255.0, pixel[0] as f32 / 255.0)
+            };
+            texels.push(texel);
+        }
+    }
+
+    Ok((width, height, texels))
+}
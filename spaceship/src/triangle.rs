@@ -1,11 +1,14 @@
-use nalgebra_glm::{Vec3, dot};
+use nalgebra_glm::{Vec2, Vec3};
 use crate::fragment::Fragment;
 use crate::vertex::Vertex;
 use crate::line::line;
 use crate::Uniforms;
 use crate::fragment_shader::{fragment_shader, ShaderType};
+use crate::debug_view::{DebugView, attribute_color, shader_cost_color};
+use std::time::Instant;
 
-pub fn _triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
+// Aristas del triángulo como fragmentos de línea (modo de alambre)
+pub fn wireframe(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragment> {
   let mut fragments = Vec::new();
 
   fragments.extend(line(v1, v2));
@@ -21,17 +24,28 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, uniforms: &Uniforms, shad
 
   let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
 
+  // Un triángulo recortado cerca de la cámara puede proyectarse muy lejos de la pantalla:
+  // sólo se recorren los píxeles visibles del rectángulo
+  let screen_width = (uniforms.viewport_matrix[(0, 3)] * 2.0) as i32;
+  let screen_height = (uniforms.viewport_matrix[(1, 3)] * 2.0) as i32;
+  let (min_x, min_y) = (min_x.max(0), min_y.max(0));
+  let (max_x, max_y) = (max_x.min(screen_width - 1), max_y.min(screen_height - 1));
+
   let triangle_area = edge_function(&a, &b, &c);
 
+  // Relación entre el área en UV y el área en pantalla del triángulo
+  let uv_area = edge_function_2d(&v1.tex_coords, &v2.tex_coords, &v3.tex_coords).abs();
+  let uv_footprint = if triangle_area.abs() > f32::EPSILON { uv_area / triangle_area.abs() } else { 0.0 };
+
   for y in min_y..=max_y {
     for x in min_x..=max_x {
       let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
 
       let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
 
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
+      if (0.0..=1.0).contains(&w1) &&
+         (0.0..=1.0).contains(&w2) &&
+         (0.0..=1.0).contains(&w3) {
         
         let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
         let normal = normal.normalize();
@@ -40,10 +54,28 @@ pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, uniforms: &Uniforms, shad
         
         let depth = a.z * w1 + b.z * w2 + c.z * w3;
         
-        let fragment = Fragment::new(x as f32, y as f32, v1.color, depth);
-        let color = fragment_shader(&fragment, uniforms, shader_type, &position, &normal);
-
-        fragments.push(Fragment::new(x as f32, y as f32, color, depth));
+        let mut fragment = Fragment::new(x as f32, y as f32, v1.color, depth);
+        fragment.tex_coords = v1.tex_coords * w1 + v2.tex_coords * w2 + v3.tex_coords * w3;
+        fragment.uv_footprint = uv_footprint;
+        fragment.world_position = v1.world_position * w1 + v2.world_position * w2 + v3.world_position * w3;
+        fragment.tangent = v1.transformed_tangent * w1 + v2.transformed_tangent * w2 + v3.transformed_tangent * w3;
+        let (color, alpha) = match uniforms.debug_view {
+          DebugView::Final => fragment_shader(&fragment, uniforms, shader_type, &position, &normal),
+          DebugView::ShaderCost => {
+            let start = Instant::now();
+            let (_, alpha) = fragment_shader(&fragment, uniforms, shader_type, &position, &normal);
+            (shader_cost_color(start.elapsed()), alpha)
+          }
+          // El resto de modos no necesita sombrear el fragmento
+          view => (attribute_color(view, &fragment, &normal, &position), 1.0),
+        };
+        if alpha <= 0.0 {
+          continue;
+        }
+
+        let mut shaded = Fragment::new(x as f32, y as f32, color, depth);
+        shaded.alpha = alpha;
+        fragments.push(shaded);
       }
     }
   }
@@ -71,3 +103,7 @@ fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) ->
 fn edge_function(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
     (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
 }
+
+fn edge_function_2d(a: &Vec2, b: &Vec2, c: &Vec2) -> f32 {
+    (c.x - a.x) * (b.y - a.y) - (c.y - a.y) * (b.x - a.x)
+}
@@ -1,4 +1,4 @@
-use nalgebra_glm::{Vec2, Vec3};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
 use crate::color::Color;
 
 #[derive(Clone, Debug)]
@@ -7,8 +7,12 @@ pub struct Vertex {
   pub normal: Vec3,
   pub tex_coords: Vec2,
   pub color: Color,
+  // xyz = tangente, w = signo de la bitangente (mano del espacio UV)
+  pub tangent: Vec4,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  pub transformed_tangent: Vec4,
+  pub world_position: Vec3,
 }
 
 impl Vertex {
@@ -18,26 +22,13 @@ impl Vertex {
       normal,
       tex_coords,
       color: Color::black(),
+      tangent: Vec4::new(0.0, 0.0, 0.0, 1.0),
       transformed_position: position,
       transformed_normal: normal,
+      transformed_tangent: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      world_position: position,
     }
   }
-
-  pub fn new_with_color(position: Vec3, color: Color) -> Self {
-    Vertex {
-      position,
-      normal: Vec3::new(0.0, 0.0, 0.0),
-      tex_coords: Vec2::new(0.0, 0.0),
-      color,
-      transformed_position: Vec3::new(0.0, 0.0, 0.0),
-      transformed_normal: Vec3::new(0.0, 0.0, 0.0),
-    }
-  }
-
-  pub fn set_transformed(&mut self, position: Vec3, normal: Vec3) {
-    self.transformed_position = position;
-    self.transformed_normal = normal;
-  }
 }
 
 impl Default for Vertex {
@@ -47,8 +38,11 @@ impl Default for Vertex {
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
       color: Color::black(),
+      tangent: Vec4::new(0.0, 0.0, 0.0, 1.0),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      transformed_tangent: Vec4::new(0.0, 0.0, 0.0, 1.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
     }
   }
 }